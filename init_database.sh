@@ -16,6 +16,11 @@ CREATE TABLE IF NOT EXISTS email_requests (
     scheduled_at DATETIME NOT NULL,
     status TINYINT NOT NULL DEFAULT 0,
     error VARCHAR(255) DEFAULT NULL,
+    leased_until DATETIME DEFAULT NULL,
+    lease_id VARCHAR(32) DEFAULT NULL,
+    template_id INTEGER DEFAULT NULL,
+    template_version INTEGER DEFAULT NULL,
+    track_clicks BOOLEAN NOT NULL DEFAULT 1,
     created_at DATETIME NOT NULL DEFAULT (datetime('now')),
     updated_at DATETIME NOT NULL DEFAULT (datetime('now')),
     deleted_at DATETIME
@@ -25,19 +30,188 @@ CREATE INDEX idx_requests_status ON email_requests(status);
 CREATE INDEX idx_requests_scheduled_at ON email_requests(scheduled_at DESC);
 CREATE INDEX idx_requests_topic_id ON email_requests(topic_id);
 CREATE INDEX idx_email_requests_message_id ON email_requests(message_id);
+CREATE INDEX idx_requests_leased_until ON email_requests(leased_until);
 
 CREATE TABLE IF NOT EXISTS email_results (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
     request_id INTEGER NOT NULL,
     status VARCHAR(50) NOT NULL,
     raw TEXT,
+    bounce_type VARCHAR(50) DEFAULT NULL,
+    bounce_sub_type VARCHAR(50) DEFAULT NULL,
+    complaint_feedback_type VARCHAR(50) DEFAULT NULL,
+    diagnostic_code TEXT DEFAULT NULL,
+    reject_reason VARCHAR(255) DEFAULT NULL,
+    delay_type VARCHAR(50) DEFAULT NULL,
+    link TEXT DEFAULT NULL,
+    event_at VARCHAR(50) DEFAULT NULL,
     created_at DATETIME NOT NULL DEFAULT (datetime('now')),
     FOREIGN KEY (request_id) REFERENCES email_requests(id)
 );
 
 CREATE INDEX idx_results_status ON email_results(status);
+CREATE INDEX idx_results_bounce_type ON email_results(bounce_type, bounce_sub_type);
 EOF
   echo "Database initialized."
 else
-  echo "Database file already exists."
+  echo "Database file already exists. Applying upgrades..."
+  # Columns added after the initial schema (errors for already applied steps are ignored)
+  sqlite3 "$DB_FILE" "ALTER TABLE email_requests ADD COLUMN leased_until DATETIME DEFAULT NULL;" 2>/dev/null
+  sqlite3 "$DB_FILE" "CREATE INDEX IF NOT EXISTS idx_requests_leased_until ON email_requests(leased_until);"
+  sqlite3 "$DB_FILE" "ALTER TABLE email_requests ADD COLUMN template_id INTEGER DEFAULT NULL;" 2>/dev/null
+  sqlite3 "$DB_FILE" "ALTER TABLE email_requests ADD COLUMN template_version INTEGER DEFAULT NULL;" 2>/dev/null
+  sqlite3 "$DB_FILE" "ALTER TABLE email_requests ADD COLUMN track_clicks BOOLEAN NOT NULL DEFAULT 1;" 2>/dev/null
+  sqlite3 "$DB_FILE" "ALTER TABLE email_results ADD COLUMN bounce_type VARCHAR(50) DEFAULT NULL;" 2>/dev/null
+  sqlite3 "$DB_FILE" "ALTER TABLE email_results ADD COLUMN bounce_sub_type VARCHAR(50) DEFAULT NULL;" 2>/dev/null
+  sqlite3 "$DB_FILE" "ALTER TABLE email_results ADD COLUMN complaint_feedback_type VARCHAR(50) DEFAULT NULL;" 2>/dev/null
+  sqlite3 "$DB_FILE" "ALTER TABLE email_results ADD COLUMN diagnostic_code TEXT DEFAULT NULL;" 2>/dev/null
+  sqlite3 "$DB_FILE" "ALTER TABLE email_results ADD COLUMN reject_reason VARCHAR(255) DEFAULT NULL;" 2>/dev/null
+  sqlite3 "$DB_FILE" "ALTER TABLE email_results ADD COLUMN delay_type VARCHAR(50) DEFAULT NULL;" 2>/dev/null
+  sqlite3 "$DB_FILE" "ALTER TABLE email_results ADD COLUMN link TEXT DEFAULT NULL;" 2>/dev/null
+  sqlite3 "$DB_FILE" "ALTER TABLE email_results ADD COLUMN event_at VARCHAR(50) DEFAULT NULL;" 2>/dev/null
+  sqlite3 "$DB_FILE" "CREATE INDEX IF NOT EXISTS idx_results_bounce_type ON email_results(bounce_type, bounce_sub_type);"
+  # Backfill the structured columns of bounces and complaints received before they existed
+  sqlite3 "$DB_FILE" "UPDATE email_results SET bounce_type = json_extract(raw, '\$.bounce.bounceType'), bounce_sub_type = json_extract(raw, '\$.bounce.bounceSubType') WHERE status = 'Bounce' AND bounce_type IS NULL AND json_valid(raw);"
+  sqlite3 "$DB_FILE" "UPDATE email_results SET complaint_feedback_type = json_extract(raw, '\$.complaint.complaintFeedbackType') WHERE status = 'Complaint' AND complaint_feedback_type IS NULL AND json_valid(raw);"
+  sqlite3 "$DB_FILE" "ALTER TABLE topics ADD COLUMN paused_at DATETIME DEFAULT NULL;" 2>/dev/null
+  sqlite3 "$DB_FILE" "ALTER TABLE email_requests ADD COLUMN lease_id VARCHAR(32) DEFAULT NULL;" 2>/dev/null
+  sqlite3 "$DB_FILE" "ALTER TABLE topics ADD COLUMN rate_breached_at DATETIME DEFAULT NULL;" 2>/dev/null
+  echo "Database upgraded."
 fi
+
+# Tables added after the initial schema (created for both new and existing databases)
+sqlite3 "$DB_FILE" <<EOF
+CREATE TABLE IF NOT EXISTS templates (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR(255) NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    updated_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    deleted_at DATETIME
+);
+
+CREATE TABLE IF NOT EXISTS template_versions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    template_id INTEGER NOT NULL,
+    version INTEGER NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    content TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (template_id) REFERENCES templates(id),
+    UNIQUE (template_id, version)
+);
+
+CREATE TABLE IF NOT EXISTS suppressions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    email VARCHAR(255) NOT NULL UNIQUE,
+    reason VARCHAR(255) NOT NULL,
+    source VARCHAR(50) NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE TABLE IF NOT EXISTS sns_subscriptions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    topic_arn VARCHAR(255) NOT NULL UNIQUE,
+    status VARCHAR(20) NOT NULL,
+    error VARCHAR(255) DEFAULT NULL,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    updated_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    confirmed_at DATETIME DEFAULT NULL
+);
+
+CREATE TABLE IF NOT EXISTS processed_events (
+    message_id VARCHAR(255) PRIMARY KEY,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_processed_events_created_at ON processed_events(created_at);
+
+CREATE TABLE IF NOT EXISTS orphan_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    sns_message_id VARCHAR(255) NOT NULL,
+    ses_message_id VARCHAR(255) NOT NULL,
+    event_type VARCHAR(50) NOT NULL,
+    raw TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_orphan_events_ses_message_id ON orphan_events(ses_message_id);
+
+CREATE TABLE IF NOT EXISTS webhooks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    url TEXT NOT NULL,
+    secret VARCHAR(255) NOT NULL,
+    topic_id VARCHAR(255) DEFAULT NULL,
+    event_types VARCHAR(255) DEFAULT NULL,
+    last_result_id INTEGER NOT NULL DEFAULT 0,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    deleted_at DATETIME
+);
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    webhook_id INTEGER NOT NULL,
+    result_id INTEGER NOT NULL,
+    payload TEXT NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    last_status_code INTEGER DEFAULT NULL,
+    last_error TEXT DEFAULT NULL,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    updated_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    delivered_at DATETIME DEFAULT NULL,
+    FOREIGN KEY (webhook_id) REFERENCES webhooks(id),
+    UNIQUE (webhook_id, result_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_status ON webhook_deliveries(status, next_attempt_at);
+
+CREATE INDEX IF NOT EXISTS idx_results_request_id ON email_results(request_id);
+
+CREATE INDEX IF NOT EXISTS idx_requests_email ON email_requests(email);
+
+CREATE TABLE IF NOT EXISTS topics (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    topic_id VARCHAR(255) NOT NULL UNIQUE,
+    name VARCHAR(255) DEFAULT NULL,
+    description TEXT DEFAULT NULL,
+    tags TEXT DEFAULT NULL,
+    created_by VARCHAR(255) DEFAULT NULL,
+    first_sent_at DATETIME DEFAULT NULL,
+    last_sent_at DATETIME DEFAULT NULL,
+    paused_at DATETIME DEFAULT NULL,
+    rate_breached_at DATETIME DEFAULT NULL,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+    updated_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Topics used before the topics table existed
+INSERT OR IGNORE INTO topics (topic_id, first_sent_at, last_sent_at, created_at, updated_at)
+SELECT topic_id,
+       MIN(CASE WHEN status = 2 THEN updated_at END),
+       MAX(CASE WHEN status = 2 THEN updated_at END),
+       MIN(created_at),
+       MIN(created_at)
+FROM email_requests
+WHERE topic_id != ''
+GROUP BY topic_id;
+
+CREATE TABLE IF NOT EXISTS audit_logs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    actor VARCHAR(255) NOT NULL,
+    action VARCHAR(50) NOT NULL,
+    target_type VARCHAR(50) NOT NULL,
+    target_id VARCHAR(255) NOT NULL,
+    details TEXT DEFAULT NULL,
+    created_at DATETIME NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_logs_target ON audit_logs(target_type, target_id);
+
+-- Sending pause set manually or by the rate guard; kept across restarts
+CREATE TABLE IF NOT EXISTS sending_pause (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    reason TEXT NOT NULL,
+    paused_at DATETIME NOT NULL
+);
+EOF
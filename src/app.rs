@@ -1,7 +1,7 @@
 use crate::handlers;
 use crate::middlewares;
 use crate::state;
-use axum::routing::delete;
+use axum::routing::{delete, patch, put};
 use axum::{
     middleware::from_fn,
     routing::{get, post},
@@ -18,7 +18,131 @@ pub async fn app(state: state::AppState) -> Result<Router, sqlx::Error> {
             post(handlers::message_handlers::create_message_handler)
                 .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
         )
+        .route(
+            "/v1/messages",
+            get(handlers::message_handlers::list_messages_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/messages/{request_id}",
+            get(handlers::message_handlers::retrieve_message_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/messages/{request_id}",
+            patch(handlers::message_handlers::reschedule_message_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/messages/{request_id}",
+            delete(handlers::message_handlers::cancel_message_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/messages/ses/{message_id}",
+            get(handlers::message_handlers::retrieve_message_by_message_id_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        // Templates
+        .route(
+            "/v1/templates",
+            post(handlers::template_handlers::create_template_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/templates",
+            get(handlers::template_handlers::list_templates_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/templates/{template_id}",
+            get(handlers::template_handlers::retrieve_template_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/templates/{template_id}",
+            put(handlers::template_handlers::update_template_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/templates/{template_id}",
+            delete(handlers::template_handlers::delete_template_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/templates/{template_id}/versions",
+            get(handlers::template_handlers::list_template_versions_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        // Sender
+        .route(
+            "/v1/sender",
+            get(handlers::sender_handlers::retrieve_sender_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/sender/pause",
+            post(handlers::sender_handlers::pause_sender_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/sender/resume",
+            post(handlers::sender_handlers::resume_sender_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        // Suppressions
+        .route(
+            "/v1/suppressions",
+            post(handlers::suppression_handlers::create_suppression_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/suppressions",
+            get(handlers::suppression_handlers::list_suppressions_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/suppressions/{email}",
+            delete(handlers::suppression_handlers::delete_suppression_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        // Webhooks
+        .route(
+            "/v1/webhooks",
+            post(handlers::webhook_handlers::create_webhook_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/webhooks",
+            get(handlers::webhook_handlers::list_webhooks_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/webhooks/{webhook_id}",
+            delete(handlers::webhook_handlers::delete_webhook_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/webhooks/deliveries",
+            get(handlers::webhook_handlers::list_deliveries_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/webhooks/deliveries/{delivery_id}/retry",
+            post(handlers::webhook_handlers::retry_delivery_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
         // Topics
+        .route(
+            "/v1/topics",
+            post(handlers::topic_handlers::create_topic_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/topics",
+            get(handlers::topic_handlers::list_topics_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
         .route(
             "/v1/topics/{topic_id}",
             get(handlers::topic_handlers::retrieve_topic_handler)
@@ -29,11 +153,41 @@ pub async fn app(state: state::AppState) -> Result<Router, sqlx::Error> {
             delete(handlers::topic_handlers::stop_topic_handler)
                 .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
         )
+        .route(
+            "/v1/topics/{topic_id}",
+            patch(handlers::topic_handlers::reschedule_topic_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/topics/{topic_id}/pause",
+            post(handlers::topic_handlers::pause_topic_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/topics/{topic_id}/resume",
+            post(handlers::topic_handlers::resume_topic_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/topics/{topic_id}/restart",
+            post(handlers::topic_handlers::restart_topic_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        // Audit logs
+        .route(
+            "/v1/audit-logs",
+            get(handlers::audit_handlers::list_audit_logs_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
         // Events
         .route(
             "/v1/events/open",
             get(handlers::event_handlers::open_message_handler),
         )
+        .route(
+            "/v1/events/click",
+            get(handlers::event_handlers::click_message_handler),
+        )
         .route(
             "/v1/events/counts/sent",
             get(handlers::event_handlers::get_sent_count_handler)
@@ -43,6 +197,16 @@ pub async fn app(state: state::AppState) -> Result<Router, sqlx::Error> {
             "/v1/events/results",
             post(handlers::event_handlers::create_event_handler),
         )
+        .route(
+            "/v1/events/subscriptions",
+            get(handlers::event_handlers::list_subscriptions_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
+        .route(
+            "/v1/events/orphans",
+            get(handlers::event_handlers::list_orphan_events_handler)
+                .layer(from_fn(middlewares::auth_middlewares::jwt_auth_middleware)),
+        )
         .with_state(state)
         .layer(TraceLayer::new_for_http());
     Ok(app)
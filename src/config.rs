@@ -12,6 +12,34 @@ pub struct Environment {
     pub aws_ses_from_email: String,
     pub max_send_per_second: i32,
     pub sentry_dsn: String,
+    pub email_transport: String,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_username: String,
+    pub smtp_password: String,
+    pub smtp_starttls: bool,
+    pub email_sink_path: Option<String>,
+    pub outbox_lease_seconds: i64,
+    pub shutdown_timeout_seconds: u64,
+    pub tracking_secret: String,
+    pub tracking_token_ttl_days: u64,
+    pub bounce_rate_threshold: f64,
+    pub complaint_rate_threshold: f64,
+    pub rate_guard_window_minutes: i64,
+    pub rate_guard_min_sent: i64,
+    pub rate_guard_pause_topics: bool,
+    pub sns_verify_signature: bool,
+    pub sns_cert_hosts: Vec<String>,
+    pub sns_topic_arns: Vec<String>,
+    pub sqs_queue_url: Option<String>,
+    pub sqs_endpoint_url: Option<String>,
+    pub sqs_wait_time_seconds: i32,
+    pub sqs_max_messages: i32,
+    pub event_dedup_retention_days: i64,
+    pub orphan_event_retention_days: i64,
+    pub webhook_max_attempts: i64,
+    pub webhook_backoff_seconds: i64,
+    pub webhook_timeout_seconds: u64,
 }
 
 // Initialize and load the .env file only upon its first access using Lazy to create the Environment instance
@@ -31,9 +59,101 @@ static ENVIRONMENTS: Lazy<Environment> = Lazy::new(|| {
             .parse::<i32>()
             .unwrap_or(24),
         sentry_dsn: env::var("SENTRY_DSN").unwrap_or_else(|_| "".to_string()),
+        email_transport: env::var("EMAIL_TRANSPORT").unwrap_or_else(|_| "ses".to_string()),
+        smtp_host: env::var("SMTP_HOST").unwrap_or_else(|_| "localhost".to_string()),
+        smtp_port: env::var("SMTP_PORT")
+            .unwrap_or_else(|_| "1025".to_string())
+            .parse::<u16>()
+            .unwrap_or(1025),
+        smtp_username: env::var("SMTP_USERNAME").unwrap_or_else(|_| "".to_string()),
+        smtp_password: env::var("SMTP_PASSWORD").unwrap_or_else(|_| "".to_string()),
+        smtp_starttls: env::var("SMTP_STARTTLS")
+            .map(|v| v == "true")
+            .unwrap_or(false),
+        email_sink_path: env::var("EMAIL_SINK_PATH").ok(),
+        outbox_lease_seconds: env::var("OUTBOX_LEASE_SECONDS")
+            .unwrap_or_else(|_| "900".to_string())
+            .parse::<i64>()
+            .unwrap_or(900),
+        shutdown_timeout_seconds: env::var("SHUTDOWN_TIMEOUT_SECONDS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse::<u64>()
+            .unwrap_or(30),
+        tracking_secret: env::var("TRACKING_SECRET").unwrap_or_else(|_| "".to_string()),
+        tracking_token_ttl_days: env::var("TRACKING_TOKEN_TTL_DAYS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse::<u64>()
+            .unwrap_or(0),
+        bounce_rate_threshold: env::var("BOUNCE_RATE_THRESHOLD")
+            .unwrap_or_else(|_| "0.05".to_string())
+            .parse::<f64>()
+            .unwrap_or(0.05),
+        complaint_rate_threshold: env::var("COMPLAINT_RATE_THRESHOLD")
+            .unwrap_or_else(|_| "0.001".to_string())
+            .parse::<f64>()
+            .unwrap_or(0.001),
+        rate_guard_window_minutes: env::var("RATE_GUARD_WINDOW_MINUTES")
+            .unwrap_or_else(|_| "1440".to_string())
+            .parse::<i64>()
+            .unwrap_or(1440),
+        rate_guard_min_sent: env::var("RATE_GUARD_MIN_SENT")
+            .unwrap_or_else(|_| "100".to_string())
+            .parse::<i64>()
+            .unwrap_or(100),
+        rate_guard_pause_topics: env::var("RATE_GUARD_PAUSE_TOPICS")
+            .map(|v| v != "false")
+            .unwrap_or(true),
+        sns_verify_signature: env::var("SNS_VERIFY_SIGNATURE")
+            .map(|v| v != "false")
+            .unwrap_or(true),
+        sns_cert_hosts: env_list("SNS_CERT_HOSTS"),
+        sns_topic_arns: env_list("SNS_TOPIC_ARNS"),
+        sqs_queue_url: env::var("SQS_QUEUE_URL").ok().filter(|v| !v.is_empty()),
+        sqs_endpoint_url: env::var("SQS_ENDPOINT_URL").ok().filter(|v| !v.is_empty()),
+        sqs_wait_time_seconds: env::var("SQS_WAIT_TIME_SECONDS")
+            .unwrap_or_else(|_| "20".to_string())
+            .parse::<i32>()
+            .unwrap_or(20),
+        sqs_max_messages: env::var("SQS_MAX_MESSAGES")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<i32>()
+            .unwrap_or(10),
+        event_dedup_retention_days: env::var("EVENT_DEDUP_RETENTION_DAYS")
+            .unwrap_or_else(|_| "14".to_string())
+            .parse::<i64>()
+            .unwrap_or(14),
+        orphan_event_retention_days: env::var("ORPHAN_EVENT_RETENTION_DAYS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse::<i64>()
+            .unwrap_or(30),
+        webhook_max_attempts: env::var("WEBHOOK_MAX_ATTEMPTS")
+            .unwrap_or_else(|_| "8".to_string())
+            .parse::<i64>()
+            .unwrap_or(8),
+        webhook_backoff_seconds: env::var("WEBHOOK_BACKOFF_SECONDS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse::<i64>()
+            .unwrap_or(30),
+        webhook_timeout_seconds: env::var("WEBHOOK_TIMEOUT_SECONDS")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<u64>()
+            .unwrap_or(10),
     }
 });
 
+/// env_list
+/// Reads a comma-separated environment variable, skipping empty entries
+fn env_list(key: &str) -> Vec<String> {
+    env::var(key)
+        .map(|v| {
+            v.split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 /// get_environments
 /// Returns the Environment structure instance
 pub fn get_environments() -> &'static Environment {
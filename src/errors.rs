@@ -0,0 +1,151 @@
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{FromRequest, Request};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// ApiError
+/// Error response shared by every handler
+/// Serialized as {"code": "...", "message": "...", "field": "..."}; `field` is null when the error is not tied to an input field
+#[derive(Serialize, Debug)]
+pub struct ApiError {
+    #[serde(skip)]
+    pub status: StatusCode,
+    pub code: &'static str,
+    pub message: String,
+    pub field: Option<String>,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+            field: None,
+        }
+    }
+
+    /// with_field
+    /// Input field the error refers to, e.g. `messages[0].subject`
+    pub fn with_field(mut self, field: impl Into<String>) -> Self {
+        self.field = Some(field.into());
+        self
+    }
+
+    /// bad_request
+    pub fn bad_request(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, code, message)
+    }
+
+    /// unauthorized
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "unauthorized", message)
+    }
+
+    /// forbidden
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, "forbidden", message)
+    }
+
+    /// not_found
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "not_found", message)
+    }
+
+    /// conflict
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT, "conflict", message)
+    }
+
+    /// internal
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", message)
+    }
+
+    /// unavailable
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::SERVICE_UNAVAILABLE, "unavailable", message)
+    }
+
+    /// database
+    /// Maps a database error to a response: missing rows are 404, constraint violations 409,
+    /// an exhausted or closed pool 503, anything else 500
+    pub fn database(error: &sqlx::Error, message: impl Into<String>) -> Self {
+        match error {
+            sqlx::Error::RowNotFound => Self::not_found(message),
+            sqlx::Error::Database(e) if e.is_unique_violation() => Self::conflict(message),
+            sqlx::Error::Database(e) if e.is_foreign_key_violation() || e.is_check_violation() => {
+                Self::bad_request("constraint_violation", message)
+            }
+            sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed => Self::unavailable(message),
+            _ => Self::internal(message),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self)).into_response()
+    }
+}
+
+/// ApiJson
+/// JSON body extractor whose rejections (malformed JSON, missing fields, too large bodies)
+/// are returned as ApiError
+pub struct ApiJson<T>(pub T);
+
+impl<S, T> FromRequest<S> for ApiJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(req, state).await {
+            Ok(Json(value)) => Ok(ApiJson(value)),
+            Err(rejection) => Err(json_rejection(rejection)),
+        }
+    }
+}
+
+/// json_rejection
+fn json_rejection(rejection: JsonRejection) -> ApiError {
+    let code = match &rejection {
+        JsonRejection::JsonDataError(_) => "invalid_field",
+        JsonRejection::JsonSyntaxError(_) => "invalid_json",
+        JsonRejection::MissingJsonContentType(_) => "unsupported_media_type",
+        _ => "invalid_body",
+    };
+    ApiError::new(rejection.status(), code, rejection.body_text())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_error_schema() {
+        let error = ApiError::bad_request("required", "subject is required")
+            .with_field("messages[0].subject");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "code": "required",
+                "message": "subject is required",
+                "field": "messages[0].subject"
+            })
+        );
+        assert_eq!(
+            ApiError::database(&sqlx::Error::RowNotFound, "Not found").status,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            ApiError::database(&sqlx::Error::PoolTimedOut, "Busy").status,
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+}
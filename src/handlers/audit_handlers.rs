@@ -0,0 +1,41 @@
+use crate::errors::ApiError;
+use crate::models::audit_log::AuditLog;
+use crate::state::AppState;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+
+/// ListAuditLogsQueryParams
+/// Query parameters for listing audit logs
+#[derive(Deserialize)]
+pub struct ListAuditLogsQueryParams {
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// list_audit_logs_handler
+/// Audit log listing handler
+/// Returns changes made through the API, newest first
+pub async fn list_audit_logs_handler(
+    State(state): State<AppState>,
+    Query(query): Query<ListAuditLogsQueryParams>,
+) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    match AuditLog::list(
+        &state.db_pool,
+        query.target_type.as_deref(),
+        query.target_id.as_deref(),
+        limit,
+    )
+    .await
+    {
+        Ok(audit_logs) => (StatusCode::OK, Json(audit_logs)).into_response(),
+        Err(e) => {
+            eprintln!("Failed to list audit logs: {:?}", e);
+            ApiError::database(&e, "Failed to list audit logs").into_response()
+        }
+    }
+}
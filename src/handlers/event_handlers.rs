@@ -1,10 +1,16 @@
+use crate::errors::ApiError;
+use crate::models::orphan_event::OrphanEvent;
 use crate::models::request::EmailRequest;
 use crate::models::result::EmailResult;
+use crate::models::subscription::SnsSubscription;
+use crate::services::events::{self, EventError, EventOutcome};
+use crate::services::sns::{self, ConfirmOutcome};
+use crate::services::tracking;
 use crate::state::AppState;
 use axum::extract::Request;
 use axum::{
     extract::{Json, Query, State},
-    http::header::HeaderValue,
+    http::header::{HeaderValue, LOCATION},
     http::HeaderMap,
     http::StatusCode,
     response::IntoResponse,
@@ -21,7 +27,14 @@ const MAX_BODY_SIZE: usize = 1024 * 1024;
 /// Query parameters for handling open events
 #[derive(Deserialize)]
 pub struct OpenMessageQueryParams {
-    pub request_id: Option<String>,
+    pub token: Option<String>,
+}
+
+/// ClickMessageQueryParams
+/// Query parameters for handling click events
+#[derive(Deserialize)]
+pub struct ClickMessageQueryParams {
+    pub token: Option<String>,
 }
 
 /// GetSentCountQueryParams
@@ -38,6 +51,13 @@ pub struct GetSentCountResponse {
     pub count: i32,
 }
 
+/// ListOrphanEventsQueryParams
+/// Query parameters for listing orphan events
+#[derive(Deserialize)]
+pub struct ListOrphanEventsQueryParams {
+    pub limit: Option<i64>,
+}
+
 /// CreateEventRequest
 /// Request for creating events
 #[derive(Deserialize, Serialize, Debug)]
@@ -46,6 +66,8 @@ enum CreateEventRequest {
     SubscriptionConfirmation {
         #[serde(rename = "SubscribeURL")]
         subscribe_url: String,
+        #[serde(rename = "TopicArn")]
+        topic_arn: String,
     },
     Notification {
         #[serde(rename = "Message")]
@@ -56,48 +78,32 @@ enum CreateEventRequest {
     Other(Value),
 }
 
-/// CreateEventNotification
-/// Notification for creating events
-#[derive(Deserialize, Debug)]
-struct CreateEventNotification {
-    #[serde(rename = "notificationType")]
-    event_type: String,
-
-    #[serde(flatten)]
-    other_fields: Value,
-}
-
 /// open_message_handler
 /// Handler for processing open events
-/// Checks if the email has been opened and saves the result
+/// Verifies the signed token and saves the open result
 /// Returns a 1x1 transparent image
 pub async fn open_message_handler(
     State(state): State<AppState>,
     Query(query): Query<OpenMessageQueryParams>,
 ) -> impl IntoResponse {
-    if let Some(request_id) = query.request_id {
-        // Save open result
-        let request_id = request_id.parse();
-        match request_id {
-            Ok(id) => {
-                // Save data if request_id is valid
-                let result = EmailResult {
-                    id: None,
-                    status: "Open".to_string(),
-                    request_id: id,
-                    raw: None,
-                };
-                match result.save(&state.db_pool).await {
-                    Err(e) => {
-                        eprintln!("Failed to save open event: {:?}", e);
-                    }
-                    _ => { /* Do nothing */ }
-                }
+    // Only signed tokens are recorded; forged or expired tokens still get the image
+    match query.token.as_deref().map(tracking::verify_open_token) {
+        Some(Some(claims)) => {
+            let result = EmailResult {
+                id: None,
+                status: "Open".to_string(),
+                request_id: claims.rid,
+                raw: None,
+                ..Default::default()
+            };
+            if let Err(e) = result.save(&state.db_pool).await {
+                eprintln!("Failed to save open event: {:?}", e);
             }
-            Err(e) => {
-                eprintln!("Failed to parse request_id: {:?}", e);
-            }
-        };
+        }
+        Some(None) => {
+            eprintln!("Invalid open tracking token");
+        }
+        None => { /* Do nothing */ }
     }
     // Return a 1x1 transparent image
     let png_bytes: &[u8] = &[
@@ -112,6 +118,44 @@ pub async fn open_message_handler(
     (StatusCode::OK, headers, png_bytes).into_response()
 }
 
+/// click_message_handler
+/// Handler for processing click events
+/// Verifies the tracking token, saves the clicked URL and redirects to it
+pub async fn click_message_handler(
+    State(state): State<AppState>,
+    Query(query): Query<ClickMessageQueryParams>,
+) -> impl IntoResponse {
+    let Some(claims) = query
+        .token
+        .as_deref()
+        .and_then(tracking::verify_click_token)
+    else {
+        return ApiError::bad_request("invalid_token", "Invalid token")
+            .with_field("token")
+            .into_response();
+    };
+
+    // Only record clicks that are actually redirected
+    let Ok(location) = HeaderValue::from_str(&claims.url) else {
+        return ApiError::bad_request("invalid_url", "Invalid URL").into_response();
+    };
+    let result = EmailResult {
+        id: None,
+        status: "Click".to_string(),
+        request_id: claims.rid,
+        raw: Some(claims.url.clone()),
+        link: Some(claims.url.clone()),
+        ..Default::default()
+    };
+    if let Err(e) = result.save(&state.db_pool).await {
+        eprintln!("Failed to save click event: {:?}", e);
+    }
+
+    let mut headers = HeaderMap::new();
+    headers.insert(LOCATION, location);
+    (StatusCode::FOUND, headers).into_response()
+}
+
 /// get_sent_count_handler
 /// Handler for retrieving recently sent email count
 /// Queries and returns the count of recently sent emails
@@ -124,11 +168,48 @@ pub async fn get_sent_count_handler(
         Ok(count) => (StatusCode::OK, Json(GetSentCountResponse { count })).into_response(),
         Err(e) => {
             eprintln!("Failed to retrieve sent count: {:?}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to retrieve sent count",
-            )
-                .into_response()
+            ApiError::database(&e, "Failed to retrieve sent count").into_response()
+        }
+    }
+}
+
+/// list_subscriptions_handler
+/// SNS subscription listing handler
+/// Returns the confirmation state of every topic that tried to subscribe
+pub async fn list_subscriptions_handler(State(state): State<AppState>) -> impl IntoResponse {
+    match SnsSubscription::list(&state.db_pool).await {
+        Ok(subscriptions) => (StatusCode::OK, Json(subscriptions)).into_response(),
+        Err(e) => {
+            eprintln!("Failed to list subscriptions: {:?}", e);
+            ApiError::database(&e, "Failed to list subscriptions").into_response()
+        }
+    }
+}
+
+/// list_orphan_events_handler
+/// Orphan event listing handler
+/// Returns events that still do not match any request, oldest first
+pub async fn list_orphan_events_handler(
+    State(state): State<AppState>,
+    Query(query): Query<ListOrphanEventsQueryParams>,
+) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let count = match OrphanEvent::count(&state.db_pool).await {
+        Ok(count) => count,
+        Err(e) => {
+            eprintln!("Failed to count orphan events: {:?}", e);
+            return ApiError::database(&e, "Failed to list orphan events").into_response();
+        }
+    };
+    match OrphanEvent::list(&state.db_pool, limit).await {
+        Ok(events) => (
+            StatusCode::OK,
+            Json(serde_json::json!({"count": count, "events": events})),
+        )
+            .into_response(),
+        Err(e) => {
+            eprintln!("Failed to list orphan events: {:?}", e);
+            ApiError::database(&e, "Failed to list orphan events").into_response()
         }
     }
 }
@@ -136,6 +217,8 @@ pub async fn get_sent_count_handler(
 /// create_event_handler
 /// Event creation handler
 /// Processes events received from AWS SNS and saves the result
+/// Events for unknown message IDs are kept as orphan events and acknowledged with 200
+/// Messages without a valid SNS signature are rejected with 403 when verification is enabled
 pub async fn create_event_handler(
     State(state): State<AppState>,
     request: Request,
@@ -145,12 +228,13 @@ pub async fn create_event_handler(
         .headers()
         .get("x-amz-sns-message-type")
         .and_then(|v| v.to_str().ok())
-        .map_or(false, |msg_type| {
+        .is_some_and(|msg_type| {
             msg_type == "Notification" || msg_type == "SubscriptionConfirmation"
         })
     {
         error!("Invalid x-amz-sns-message-type header");
-        return (StatusCode::BAD_REQUEST, "Invalid SNS Message Type").into_response();
+        return ApiError::bad_request("invalid_message_type", "Invalid SNS Message Type")
+            .into_response();
     }
 
     // --- 2. Body Extraction (with size limit) ---
@@ -161,11 +245,19 @@ pub async fn create_event_handler(
                 "Failed to read request body (size limit exceeded or other error): {:?}",
                 e
             );
-            return (StatusCode::BAD_REQUEST, "Failed to read body").into_response();
+            return ApiError::bad_request("invalid_body", "Failed to read body").into_response();
         }
     };
 
-    // --- 3. Parse SNS Message ---
+    // --- 3. Signature Verification ---
+    if let Some(verifier) = &state.sns_verifier {
+        if let Err(e) = verifier.verify(&body_bytes).await {
+            error!("Rejected SNS message: {}", e);
+            return ApiError::forbidden("Invalid SNS signature").into_response();
+        }
+    }
+
+    // --- 4. Parse SNS Message ---
     let sns_message: CreateEventRequest = match serde_json::from_slice(&body_bytes) {
         Ok(msg) => msg,
         Err(e) => {
@@ -174,91 +266,71 @@ pub async fn create_event_handler(
                 e,
                 String::from_utf8_lossy(&body_bytes)
             );
-            return (StatusCode::BAD_REQUEST, "Failed to parse SNS message").into_response();
+            return ApiError::bad_request("invalid_json", "Failed to parse SNS message")
+                .into_response();
         }
     };
 
-    // --- 4. Handle Message Types ---
+    // --- 5. Handle Message Types ---
     match sns_message {
-        CreateEventRequest::SubscriptionConfirmation { subscribe_url } => {
-            info!(
-                "Subscription confirmation required. Visiting: {}",
-                subscribe_url
-            );
-            (StatusCode::OK, "Subscription confirmation required").into_response()
-        }
-        CreateEventRequest::Notification {
-            message,
-            message_id,
+        CreateEventRequest::SubscriptionConfirmation {
+            subscribe_url,
+            topic_arn,
         } => {
-            // --- 4a. Parse SES Notification directly ---
-            match serde_json::from_str::<CreateEventNotification>(&message) {
-                Ok(ses_notification) => {
-                    // --- 4b. Extract SES message_id from other_fields ---
-                    let ses_message_id = ses_notification
-                        .other_fields
-                        .get("mail")
-                        .and_then(|mail| mail.get("messageId"))
-                        .and_then(|id| id.as_str()) // Convert to &str
-                        .map(String::from); // Convert to String
-
-                    // --- 4c. Handle Event Types and Database Operations ---
-                    match ses_message_id {
-                        Some(ses_msg_id) => {
-                            match EmailRequest::get_request_id_by_message_id(
-                                &state.db_pool,
-                                &ses_msg_id,
-                            )
-                            .await
-                            {
-                                Ok(request_id) => {
-                                    let result = EmailResult {
-                                        id: None,
-                                        request_id,
-                                        status: ses_notification.event_type.clone(),
-                                        raw: Some(message),
-                                    };
-
-                                    match result.save(&state.db_pool).await {
-                                        Ok(_) => (StatusCode::OK, "OK").into_response(),
-                                        Err(e) => {
-                                            error!("Failed to save event to database: {:?}", e);
-                                            (
-                                                StatusCode::INTERNAL_SERVER_ERROR,
-                                                "Failed to save event",
-                                            )
-                                                .into_response()
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    // Log *both* SNS and SES message IDs for debugging
-                                    error!("Failed to retrieve request_id. SNS MessageId: {}, SES MessageId: {}, Error: {:?}", message_id, ses_msg_id, e);
-                                    (
-                                        StatusCode::INTERNAL_SERVER_ERROR,
-                                        "Failed to retrieve request_id",
-                                    )
-                                        .into_response()
-                                }
-                            }
-                        }
-                        None => {
-                            // --- 4d. Handle missing SES message_id ---
-                            error!("SES message_id not found in notification. SNS MessageId: {}.  Message: {}", message_id, message);
-                            (StatusCode::BAD_REQUEST, "SES message_id not found").into_response()
-                        }
-                    }
+            let envs = crate::config::get_environments();
+            match sns::confirm_subscription(
+                &state.db_pool,
+                state.http_fetcher.as_ref(),
+                &envs.sns_topic_arns,
+                &envs.sns_cert_hosts,
+                &topic_arn,
+                &subscribe_url,
+            )
+            .await
+            {
+                Ok(ConfirmOutcome::Confirmed) => {
+                    info!("Subscription confirmed: {}", topic_arn);
+                    (StatusCode::OK, "Subscription confirmed").into_response()
+                }
+                Ok(ConfirmOutcome::NotAllowed(reason)) => {
+                    error!("Subscription not confirmed: {} ({})", topic_arn, reason);
+                    ApiError::forbidden("Subscription not allowed").into_response()
+                }
+                Ok(ConfirmOutcome::Failed(reason)) => {
+                    // SNS retries the confirmation on server errors
+                    error!("Subscription not confirmed: {} ({})", topic_arn, reason);
+                    ApiError::new(
+                        StatusCode::BAD_GATEWAY,
+                        "bad_gateway",
+                        "Failed to confirm subscription",
+                    )
+                    .into_response()
                 }
                 Err(e) => {
-                    // --- 4e. Handle Non-JSON or Incorrect SES Messages ---
-                    error!(
-                        "Failed to parse SES notification: {:?}, message: {}",
-                        e, message
-                    ); // Log error *and* message
-                    (StatusCode::OK, "Non-SES notification received").into_response()
+                    error!("Failed to save subscription: {:?}", e);
+                    ApiError::database(&e, "Failed to save subscription").into_response()
                 }
             }
         }
+        CreateEventRequest::Notification {
+            message,
+            message_id,
+        } => match events::process_notification(&state.db_pool, &message_id, message).await {
+            Ok(EventOutcome::Saved) => (StatusCode::OK, "OK").into_response(),
+            Ok(EventOutcome::Orphaned) => {
+                (StatusCode::OK, "Event stored for reconciliation").into_response()
+            }
+            Ok(EventOutcome::Duplicate) => {
+                (StatusCode::OK, "Duplicate notification skipped").into_response()
+            }
+            Ok(EventOutcome::Ignored) => {
+                (StatusCode::OK, "Non-SES notification received").into_response()
+            }
+            Err(EventError::MissingMessageId) => {
+                ApiError::bad_request("required", "SES message_id not found").into_response()
+            }
+            Err(EventError::Database) => ApiError::internal("Failed to save event").into_response(),
+        },
         CreateEventRequest::Other(_) => {
             info!("Received other message type");
             (StatusCode::OK, "Other message type received").into_response()
@@ -1,21 +1,50 @@
-use crate::models::request::{EmailMessageStatus, EmailRequest};
+use crate::errors::{ApiError, ApiJson};
+use crate::middlewares::auth_middlewares::Claims;
+use crate::models::audit_log::{
+    AuditLog, AUDIT_ACTION_MESSAGE_CANCEL, AUDIT_ACTION_MESSAGE_RESCHEDULE,
+};
+use crate::models::request::{
+    EmailMessageStatus, EmailRequest, MessageDetail, MessageFilter, MessageSummary,
+};
+use crate::models::ses_event::SesEventType;
+use crate::models::suppression::Suppression;
+use crate::models::template::EmailTemplate;
+use crate::services::renderer::MessageTemplate;
 use crate::state::AppState;
-use axum::extract::State;
-use axum::response::IntoResponse;
-use axum::Json;
+use crate::validation;
+use axum::extract::{Path, Query, State};
+use axum::{Extension, Json};
 use futures::stream::{self, StreamExt};
-use reqwest::StatusCode;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::sync::Arc;
 
+/// Recipient
+/// Recipient with its own template variables
+#[derive(Deserialize)]
+pub struct Recipient {
+    pub email: String,
+    #[serde(default)]
+    pub vars: Map<String, Value>,
+}
+
 /// Message
 /// Message used in a creation request
+/// `recipients` renders subject and content as templates against each recipient's vars
+/// `template_id` (and optionally `template_version`) uses a stored template instead of inline subject/content
+/// Links in the content are rewritten for click tracking unless `track_clicks` is false
 #[derive(Deserialize)]
 pub struct Message {
     pub topic_id: Option<String>,
+    #[serde(default)]
     pub emails: Vec<String>,
-    pub subject: String,
-    pub content: String,
+    #[serde(default)]
+    pub recipients: Vec<Recipient>,
+    pub subject: Option<String>,
+    pub content: Option<String>,
+    pub template_id: Option<i32>,
+    pub template_version: Option<i32>,
+    pub track_clicks: Option<bool>,
 }
 
 /// CreateMessageRequest
@@ -24,60 +53,569 @@ pub struct Message {
 pub struct CreateMessageRequest {
     pub messages: Vec<Message>,
     pub scheduled_at: Option<String>,
+    /// IANA time zone of a `scheduled_at` without an offset; the server's time zone if omitted
+    pub timezone: Option<String>,
+}
+
+/// RecipientStatus
+/// Outcome of a recipient in a creation request
+/// `queued` requests are sent as soon as possible, `scheduled` ones at their scheduled time,
+/// `paused` ones are held until their topic is resumed
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum RecipientStatus {
+    Queued,
+    Scheduled,
+    Paused,
+    Rejected,
+}
+
+/// RecipientResult
+/// Created request of a recipient
+/// `request_id` is null for recipients rejected before a request was saved (e.g. invalid addresses)
+#[derive(Serialize)]
+pub struct RecipientResult {
+    pub email: String,
+    pub topic_id: String,
+    pub request_id: Option<i32>,
+    pub status: RecipientStatus,
+    pub error: Option<String>,
+}
+
+/// CreateMessageResponse
+/// Message creation response with the outcome of every recipient, in request order
+#[derive(Serialize)]
+pub struct CreateMessageResponse {
+    pub queued: usize,
+    pub scheduled: usize,
+    pub paused: usize,
+    pub rejected: usize,
+    pub results: Vec<RecipientResult>,
+}
+
+/// ListMessagesQueryParams
+/// Query parameters for listing messages
+/// `cursor` is the `next_cursor` of the previous page
+#[derive(Deserialize)]
+pub struct ListMessagesQueryParams {
+    pub topic_id: Option<String>,
+    pub email: Option<String>,
+    pub status: Option<String>,
+    pub created_from: Option<String>,
+    pub created_to: Option<String>,
+    pub scheduled_from: Option<String>,
+    pub scheduled_to: Option<String>,
+    pub has_event: Option<String>,
+    pub cursor: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+/// ListMessagesResponse
+/// Page of messages, newest first
+/// `next_cursor` is null on the last page
+#[derive(Serialize)]
+pub struct ListMessagesResponse {
+    pub messages: Vec<MessageSummary>,
+    pub next_cursor: Option<i64>,
+}
+
+/// RescheduleRequest
+/// Reschedule request for a message or a topic
+/// `scheduled_at` uses the same format as message creation; empty means now
+#[derive(Deserialize)]
+pub struct RescheduleRequest {
+    pub scheduled_at: String,
+    pub timezone: Option<String>,
+}
+
+/// validate_message
+/// Validates a message before any request is created
+/// Template messages are checked once the template is resolved
+fn validate_message(index: usize, message: &Message) -> Result<(), ApiError> {
+    let field = |name: &str| format!("messages[{}].{}", index, name);
+    if message.emails.is_empty() && message.recipients.is_empty() {
+        return Err(
+            ApiError::bad_request("required", "emails or recipients is required")
+                .with_field(field("emails")),
+        );
+    }
+    if message.template_id.is_some() {
+        if message.subject.is_some() || message.content.is_some() {
+            return Err(ApiError::bad_request(
+                "conflicting_fields",
+                "subject and content cannot be combined with template_id",
+            )
+            .with_field(field("template_id")));
+        }
+        return Ok(());
+    }
+    validation::required(&field("subject"), message.subject.as_deref())?;
+    validation::required(&field("content"), message.content.as_deref())?;
+    validate_lengths(index, message)
+}
+
+/// validate_lengths
+fn validate_lengths(index: usize, message: &Message) -> Result<(), ApiError> {
+    validation::max_length(
+        &format!("messages[{}].subject", index),
+        message.subject.as_deref().unwrap_or_default(),
+        validation::MAX_SUBJECT_LENGTH,
+    )?;
+    validation::max_length(
+        &format!("messages[{}].content", index),
+        message.content.as_deref().unwrap_or_default(),
+        validation::MAX_CONTENT_LENGTH,
+    )
 }
 
 /// create_message_handler
 /// Message creation handler
 /// Creates messages and processes them concurrently using a thread pool.
 /// Immediately sends if no scheduled send time is provided; otherwise, schedules the send.
+/// Invalid input rejects the whole request; rejected recipients do not abort the rest of the batch
 pub async fn create_message_handler(
     State(state): State<AppState>,
-    Json(payload): Json<CreateMessageRequest>,
-) -> impl IntoResponse {
-    let start = std::time::Instant::now();
-    let scheduled_at = payload.scheduled_at;
+    Extension(claims): Extension<Claims>,
+    ApiJson(payload): ApiJson<CreateMessageRequest>,
+) -> Result<Json<CreateMessageResponse>, ApiError> {
+    // Stop accepting new messages while shutting down
+    if state.shutdown.is_cancelled() {
+        return Err(ApiError::unavailable("Server is shutting down"));
+    }
+    if payload.messages.is_empty() {
+        return Err(
+            ApiError::bad_request("required", "messages is required").with_field("messages")
+        );
+    }
+    let recipient_count: usize = payload
+        .messages
+        .iter()
+        .map(|message| message.emails.len() + message.recipients.len())
+        .sum();
+    if recipient_count > validation::MAX_RECIPIENTS_PER_REQUEST {
+        return Err(ApiError::bad_request(
+            "too_many_recipients",
+            format!(
+                "At most {} recipients are accepted per request",
+                validation::MAX_RECIPIENTS_PER_REQUEST
+            ),
+        )
+        .with_field("messages"));
+    }
+    for (index, message) in payload.messages.iter().enumerate() {
+        validate_message(index, message)?;
+    }
+    let scheduled_at =
+        validation::scheduled_at(payload.scheduled_at.as_deref(), payload.timezone.as_deref())?;
+
+    let lease_seconds = crate::config::get_environments().outbox_lease_seconds;
     // Immediately send if no scheduled send time is provided
-    let mut status = EmailMessageStatus::Created as i32;
-    if let Some(scheduled_at) = scheduled_at.clone() {
-        if scheduled_at.is_empty() {
-            status = EmailMessageStatus::Processed as i32;
+    let mut status = match payload.scheduled_at.as_deref() {
+        Some(scheduled_at) if !scheduled_at.is_empty() => EmailMessageStatus::Created as i32,
+        _ => EmailMessageStatus::Processed as i32,
+    };
+    // While sending is paused, immediate sends wait in the outbox for the scheduler
+    let immediate = status == EmailMessageStatus::Processed as i32;
+    if state.pause.is_paused() {
+        status = EmailMessageStatus::Created as i32;
+    }
+
+    // Resolve stored templates before creating any request
+    let mut messages = Vec::with_capacity(payload.messages.len());
+    for (index, mut message) in payload.messages.into_iter().enumerate() {
+        if let Some(template_id) = message.template_id {
+            match EmailTemplate::find(&state.db_pool, template_id, message.template_version).await {
+                Ok(Some(template)) => {
+                    message.subject = Some(template.subject);
+                    message.content = Some(template.content);
+                    message.template_version = Some(template.version);
+                }
+                Ok(None) => {
+                    return Err(ApiError::not_found("Template not found")
+                        .with_field(format!("messages[{}].template_id", index)));
+                }
+                Err(e) => {
+                    eprintln!("Failed to retrieve template: {:?}", e);
+                    return Err(ApiError::database(&e, "Failed to retrieve template"));
+                }
+            }
+            validate_lengths(index, &message)?;
         }
-    } else {
-        status = EmailMessageStatus::Processed as i32;
+        messages.push(message);
     }
 
     // Process concurrently using a pool of 100 threads
-    let tasks = stream::iter(payload.messages.into_iter().flat_map(|message| {
-        let scheduled_at = scheduled_at.clone();
+    let tasks = stream::iter(messages.into_iter().flat_map(|message| {
+        let subject = message.subject.unwrap_or_default();
+        let content = message.content.unwrap_or_default();
+        // Compile the templates once per message when recipients carry variables
+        let template = if message.recipients.is_empty() {
+            None
+        } else {
+            Some(Arc::new(MessageTemplate::new(&subject, &content)))
+        };
         let request = EmailRequest {
             id: None,
             topic_id: Some(message.topic_id.unwrap_or_default()),
             error: None,
             email: String::from(""),
-            subject: message.subject,
-            content: message.content,
-            scheduled_at: scheduled_at.clone(),
+            subject,
+            content,
+            scheduled_at: Some(scheduled_at.clone()),
             status,
             message_id: None,
+            leased_until: None,
+            lease_id: None,
+            template_id: message.template_id,
+            template_version: message.template_version,
+            track_clicks: message.track_clicks.unwrap_or(true),
         };
         let db_pool = Arc::new(state.db_pool.clone());
         let tx = Arc::new(state.tx.clone());
-        message.emails.into_iter().map(move |email| {
+        // Topics used for the first time are registered with their first saved request, owned by the caller
+        let created_by = Arc::new(claims.sub.clone());
+        let recipients = message.emails.into_iter().map(|email| (email, None)).chain(
+            message
+                .recipients
+                .into_iter()
+                .map(|recipient| (recipient.email, Some(recipient.vars))),
+        );
+        recipients.map(move |(email, vars)| {
             let mut request = request.clone();
-            request.email = email.clone();
+            request.email = email.trim().to_string();
+            // Invalid addresses are rejected without saving a request
+            let invalid_email = validation::email("email", &request.email).is_err();
+            if invalid_email {
+                request.status = EmailMessageStatus::Failed as i32;
+                request.error = Some("Invalid email address".to_string());
+            } else if let (Some(vars), Some(template)) = (vars, template.as_ref()) {
+                // Rendering failures are saved as failed requests for that recipient
+                // Variables can make the message longer, so the limits are checked again after rendering
+                let rendered = match template.as_ref() {
+                    Ok(template) => template.render(&Value::Object(vars)),
+                    Err(e) => Err(e.clone()),
+                }
+                .and_then(|(subject, content)| {
+                    validation::max_length("subject", &subject, validation::MAX_SUBJECT_LENGTH)
+                        .and_then(|_| {
+                            validation::max_length(
+                                "content",
+                                &content,
+                                validation::MAX_CONTENT_LENGTH,
+                            )
+                        })
+                        .map_err(|e| format!("Rendered {}", e.message))?;
+                    Ok((subject, content))
+                });
+                match rendered {
+                    Ok((subject, content)) => {
+                        request.subject = subject;
+                        request.content = content;
+                    }
+                    Err(e) => {
+                        request.status = EmailMessageStatus::Failed as i32;
+                        request.error = Some(e);
+                    }
+                }
+            }
             let db_pool = Arc::clone(&db_pool);
             let tx = Arc::clone(&tx);
+            let created_by = Arc::clone(&created_by);
             async move {
-                let request = request.save(&db_pool).await;
-                if status == EmailMessageStatus::Processed as i32 {
+                if invalid_email {
+                    let result = RecipientResult {
+                        email,
+                        topic_id: request.topic_id.unwrap_or_default(),
+                        request_id: None,
+                        status: RecipientStatus::Rejected,
+                        error: request.error,
+                    };
+                    return (result, None);
+                }
+                // Suppressed recipients are recorded but never queued
+                if request.status != EmailMessageStatus::Failed as i32 {
+                    match Suppression::is_suppressed(&db_pool, &request.email).await {
+                        Ok(true) => {
+                            request.status = EmailMessageStatus::Suppressed as i32;
+                            request.error = Some("Recipient is suppressed".to_string());
+                        }
+                        Ok(false) => {}
+                        Err(e) => eprintln!("Failed to check suppression: {:?}", e),
+                    }
+                }
+                let topic_id = request.topic_id.clone().unwrap_or_default();
+                let request = match request
+                    .save(&db_pool, lease_seconds, Some(&created_by))
+                    .await
+                {
+                    Ok(request) => request,
+                    Err(e) => {
+                        eprintln!("Failed to save request: {:?}", e);
+                        let result = RecipientResult {
+                            email,
+                            topic_id,
+                            request_id: None,
+                            status: RecipientStatus::Rejected,
+                            error: Some("Failed to save request".to_string()),
+                        };
+                        return (result, Some(e));
+                    }
+                };
+                let result = RecipientResult {
+                    email: request.email.clone(),
+                    topic_id,
+                    request_id: request.id,
+                    status: match request.status {
+                        s if s == EmailMessageStatus::Failed as i32
+                            || s == EmailMessageStatus::Suppressed as i32 =>
+                        {
+                            RecipientStatus::Rejected
+                        }
+                        s if s == EmailMessageStatus::Paused as i32 => RecipientStatus::Paused,
+                        _ if immediate => RecipientStatus::Queued,
+                        _ => RecipientStatus::Scheduled,
+                    },
+                    error: request.error.clone(),
+                };
+                if request.status == EmailMessageStatus::Processed as i32 {
                     if let Err(e) = tx.send(request).await {
                         eprintln!("Error sending data to channel: {:?}", e);
+                        // Return the claim so the scheduler picks it up
+                        if let Err(e) = e.0.release(&db_pool).await {
+                            eprintln!("Failed to release request: {:?}", e);
+                        }
                     }
                 }
+                (result, None)
             }
         })
     }));
-    tasks.buffer_unordered(100).for_each(|_| async {}).await;
-    let duration = start.elapsed();
-    (StatusCode::OK, format!("Processed in {:?}", duration)).into_response()
+    // `buffered` keeps the results in request order
+    let outcomes: Vec<(RecipientResult, Option<sqlx::Error>)> = tasks.buffered(100).collect().await;
+    // A database that fails every save is reported as an error rather than a batch of rejections
+    if outcomes.iter().all(|(_, e)| e.is_some()) {
+        if let Some((_, Some(e))) = outcomes.first() {
+            return Err(ApiError::database(e, "Failed to save requests"));
+        }
+    }
+    let results: Vec<RecipientResult> = outcomes.into_iter().map(|(result, _)| result).collect();
+    let count = |status: RecipientStatus| results.iter().filter(|r| r.status == status).count();
+    let response = CreateMessageResponse {
+        queued: count(RecipientStatus::Queued),
+        scheduled: count(RecipientStatus::Scheduled),
+        paused: count(RecipientStatus::Paused),
+        rejected: count(RecipientStatus::Rejected),
+        results,
+    };
+    Ok(Json(response))
+}
+
+/// retrieve_message_handler
+/// Message retrieval handler
+/// Returns the request with its ordered event timeline
+pub async fn retrieve_message_handler(
+    State(state): State<AppState>,
+    Path(request_id): Path<i32>,
+) -> Result<Json<MessageDetail>, ApiError> {
+    find_message(&state, Some(request_id), None).await
+}
+
+/// retrieve_message_by_message_id_handler
+/// Message retrieval handler by SES message ID
+/// Returns the same response as `retrieve_message_handler`
+pub async fn retrieve_message_by_message_id_handler(
+    State(state): State<AppState>,
+    Path(message_id): Path<String>,
+) -> Result<Json<MessageDetail>, ApiError> {
+    find_message(&state, None, Some(&message_id)).await
+}
+
+/// find_message
+async fn find_message(
+    state: &AppState,
+    request_id: Option<i32>,
+    message_id: Option<&str>,
+) -> Result<Json<MessageDetail>, ApiError> {
+    match EmailRequest::find_detail(&state.db_pool, request_id, message_id).await {
+        Ok(Some(message)) => Ok(Json(message)),
+        Ok(None) => Err(ApiError::not_found("Message not found")),
+        Err(e) => {
+            eprintln!("Failed to retrieve message: {:?}", e);
+            Err(ApiError::database(&e, "Failed to retrieve message"))
+        }
+    }
+}
+
+/// list_messages_handler
+/// Message listing handler
+/// Filters by topic, recipient, status, created/scheduled date ranges and received event type
+pub async fn list_messages_handler(
+    State(state): State<AppState>,
+    Query(query): Query<ListMessagesQueryParams>,
+) -> Result<Json<ListMessagesResponse>, ApiError> {
+    let status = match query.status.as_deref().filter(|s| !s.is_empty()) {
+        Some(name) => match EmailMessageStatus::from_name(name) {
+            Some(status) => Some(status as i32),
+            None => {
+                return Err(ApiError::bad_request(
+                    "invalid_value",
+                    "status must be Created, Processed, Sent, Failed, Stopped, Suppressed or Paused",
+                )
+                .with_field("status"));
+            }
+        },
+        None => None,
+    };
+    let has_event = match query.has_event.as_deref().filter(|v| !v.is_empty()) {
+        Some(name) => match SesEventType::from_name(name) {
+            Some(event_type) => Some(event_type.as_str().to_string()),
+            None => {
+                return Err(ApiError::bad_request(
+                    "invalid_value",
+                    "has_event must be an event type such as Bounce, Complaint, Delivery, Open or Click",
+                )
+                .with_field("has_event"));
+            }
+        },
+        None => None,
+    };
+    let date = |field: &str, value: Option<String>| {
+        value
+            .filter(|v| !v.is_empty())
+            .map(|v| validation::utc_datetime(field, &v))
+            .transpose()
+    };
+    let filter = MessageFilter {
+        topic_id: query.topic_id.filter(|v| !v.is_empty()),
+        email: query
+            .email
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty()),
+        status,
+        created_from: date("created_from", query.created_from)?,
+        created_to: date("created_to", query.created_to)?,
+        scheduled_from: date("scheduled_from", query.scheduled_from)?,
+        scheduled_to: date("scheduled_to", query.scheduled_to)?,
+        has_event,
+    };
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    // One extra row tells whether another page follows
+    let mut messages =
+        match EmailRequest::list(&state.db_pool, &filter, query.cursor, limit + 1).await {
+            Ok(messages) => messages,
+            Err(e) => {
+                eprintln!("Failed to list messages: {:?}", e);
+                return Err(ApiError::database(&e, "Failed to list messages"));
+            }
+        };
+    let next_cursor = if messages.len() as i64 > limit {
+        messages.truncate(limit as usize);
+        messages.last().map(|message| message.id)
+    } else {
+        None
+    };
+    Ok(Json(ListMessagesResponse {
+        messages,
+        next_cursor,
+    }))
+}
+
+/// reschedule_message_handler
+/// Message reschedule handler
+/// Only messages still waiting to be sent (Created or Paused) can be rescheduled
+pub async fn reschedule_message_handler(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(request_id): Path<i32>,
+    ApiJson(payload): ApiJson<RescheduleRequest>,
+) -> Result<Json<MessageDetail>, ApiError> {
+    let scheduled_at =
+        validation::scheduled_at(Some(&payload.scheduled_at), payload.timezone.as_deref())?;
+    let Json(message) = find_message(&state, Some(request_id), None).await?;
+    let audit_log = AuditLog::new(
+        &claims.sub,
+        AUDIT_ACTION_MESSAGE_RESCHEDULE,
+        "message",
+        &request_id.to_string(),
+        serde_json::json!({
+            "scheduled_at": scheduled_at,
+            "previous_scheduled_at": message.message.scheduled_at,
+        }),
+    );
+    match reschedule_message(&state, request_id, &scheduled_at, audit_log).await {
+        Ok(true) => find_message(&state, Some(request_id), None).await,
+        Ok(false) => Err(ApiError::conflict(
+            "Only messages waiting to be sent can be rescheduled",
+        )),
+        Err(e) => {
+            eprintln!("Failed to reschedule message: {:?}", e);
+            Err(ApiError::database(&e, "Failed to reschedule message"))
+        }
+    }
+}
+
+/// reschedule_message
+/// Reschedules the request and records the change in one transaction
+async fn reschedule_message(
+    state: &AppState,
+    request_id: i32,
+    scheduled_at: &str,
+    audit_log: AuditLog,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = state.db_pool.begin().await?;
+    if !EmailRequest::reschedule(&mut *tx, request_id, scheduled_at).await? {
+        return Ok(false);
+    }
+    audit_log.save(&mut *tx).await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// cancel_message_handler
+/// Message cancellation handler
+/// Stops a message waiting to be sent; a message already claimed for sending may be in flight and is not cancelled
+pub async fn cancel_message_handler(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(request_id): Path<i32>,
+) -> Result<Json<MessageDetail>, ApiError> {
+    let Json(message) = find_message(&state, Some(request_id), None).await?;
+    let audit_log = AuditLog::new(
+        &claims.sub,
+        AUDIT_ACTION_MESSAGE_CANCEL,
+        "message",
+        &request_id.to_string(),
+        serde_json::json!({
+            "previous_status": EmailMessageStatus::name(message.message.status),
+        }),
+    );
+    match cancel_message(&state, request_id, audit_log).await {
+        Ok(true) => find_message(&state, Some(request_id), None).await,
+        Ok(false) if message.message.status == EmailMessageStatus::Processed as i64 => Err(
+            ApiError::conflict("The message is being sent and can no longer be cancelled"),
+        ),
+        Ok(false) => Err(ApiError::conflict(
+            "Only messages waiting to be sent can be cancelled",
+        )),
+        Err(e) => {
+            eprintln!("Failed to cancel message: {:?}", e);
+            Err(ApiError::database(&e, "Failed to cancel message"))
+        }
+    }
+}
+
+/// cancel_message
+/// Cancels the request and records the change in one transaction
+async fn cancel_message(
+    state: &AppState,
+    request_id: i32,
+    audit_log: AuditLog,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = state.db_pool.begin().await?;
+    if !EmailRequest::cancel(&mut *tx, request_id).await? {
+        return Ok(false);
+    }
+    audit_log.save(&mut *tx).await?;
+    tx.commit().await?;
+    Ok(true)
 }
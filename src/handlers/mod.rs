@@ -1,3 +1,8 @@
+pub mod audit_handlers;
 pub mod event_handlers;
 pub mod message_handlers;
+pub mod sender_handlers;
+pub mod suppression_handlers;
+pub mod template_handlers;
 pub mod topic_handlers;
+pub mod webhook_handlers;
@@ -0,0 +1,99 @@
+use crate::errors::ApiError;
+use crate::models::result::EmailResult;
+use crate::services::monitor::{total_stats, RateThresholds};
+use crate::state::AppState;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Deserialize;
+
+/// PauseSenderRequest
+/// Sender pause request
+#[derive(Deserialize, Default)]
+pub struct PauseSenderRequest {
+    pub reason: Option<String>,
+}
+
+/// retrieve_sender_handler
+/// Sender status handler
+/// Returns whether sending is paused and the current rolling bounce/complaint rates
+pub async fn retrieve_sender_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let thresholds = RateThresholds::from_environments();
+    let stats =
+        match EmailResult::get_delivery_stats_by_topic(&state.db_pool, thresholds.window_minutes)
+            .await
+        {
+            Ok(stats) => stats,
+            Err(e) => {
+                eprintln!("Failed to retrieve delivery stats: {:?}", e);
+                return ApiError::database(&e, "Failed to retrieve delivery stats").into_response();
+            }
+        };
+    let total = total_stats(&stats);
+    let rate = |count: i64| {
+        if total.sent == 0 {
+            0.0
+        } else {
+            count as f64 / total.sent as f64
+        }
+    };
+    let response = serde_json::json!({
+        "paused": state.pause.is_paused(),
+        "pause": state.pause.info(),
+        "window_minutes": thresholds.window_minutes,
+        "sent": total.sent,
+        "bounces": total.bounces,
+        "complaints": total.complaints,
+        "bounce_rate": rate(total.bounces),
+        "complaint_rate": rate(total.complaints),
+        "bounce_rate_threshold": thresholds.bounce_rate,
+        "complaint_rate_threshold": thresholds.complaint_rate,
+    });
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+/// pause_sender_handler
+/// Sender pause handler
+/// Holds every send until resumed; queued requests stay in the outbox
+pub async fn pause_sender_handler(State(state): State<AppState>, body: Bytes) -> impl IntoResponse {
+    // The body is optional
+    let payload = if body.is_empty() {
+        PauseSenderRequest::default()
+    } else {
+        match serde_json::from_slice::<PauseSenderRequest>(&body) {
+            Ok(payload) => payload,
+            Err(e) => return ApiError::bad_request("invalid_json", e.to_string()).into_response(),
+        }
+    };
+    let reason = payload
+        .reason
+        .unwrap_or_else(|| "Paused manually".to_string());
+    if !state.pause.pause(&reason) {
+        return ApiError::conflict("Sending is already paused").into_response();
+    }
+    persist_pause(&state).await
+}
+
+/// resume_sender_handler
+/// Sender resume handler
+/// Held requests are picked up by the scheduler on its next run
+pub async fn resume_sender_handler(State(state): State<AppState>) -> impl IntoResponse {
+    if !state.pause.resume() {
+        return ApiError::conflict("Sending is not paused").into_response();
+    }
+    persist_pause(&state).await
+}
+
+/// persist_pause
+/// Stores the pause state so it survives restarts
+async fn persist_pause(state: &AppState) -> Response {
+    match state.pause.persist(&state.db_pool).await {
+        Ok(()) => (StatusCode::OK, "OK").into_response(),
+        Err(e) => {
+            eprintln!("Failed to store sending pause: {:?}", e);
+            ApiError::database(&e, "Failed to store sending pause").into_response()
+        }
+    }
+}
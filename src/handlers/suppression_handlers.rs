@@ -0,0 +1,74 @@
+use crate::errors::{ApiError, ApiJson};
+use crate::models::suppression::{Suppression, SUPPRESSION_SOURCE_API};
+use crate::state::AppState;
+use crate::validation;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+
+/// CreateSuppressionRequest
+/// Suppression creation request
+#[derive(Deserialize)]
+pub struct CreateSuppressionRequest {
+    pub email: String,
+    pub reason: Option<String>,
+}
+
+/// create_suppression_handler
+/// Suppression creation handler
+/// Adds the address to the suppression list with the `api` source
+pub async fn create_suppression_handler(
+    State(state): State<AppState>,
+    ApiJson(payload): ApiJson<CreateSuppressionRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = validation::required("email", Some(&payload.email))
+        .and_then(|_| validation::email("email", payload.email.trim()))
+    {
+        return e.into_response();
+    }
+    let suppression = Suppression {
+        id: None,
+        email: payload.email,
+        reason: payload.reason.unwrap_or_else(|| "Manual".to_string()),
+        source: SUPPRESSION_SOURCE_API.to_string(),
+        created_at: None,
+    };
+    match suppression.save(&state.db_pool).await {
+        Ok(suppression) => (StatusCode::CREATED, Json(suppression)).into_response(),
+        Err(e) => {
+            eprintln!("Failed to create suppression: {:?}", e);
+            ApiError::database(&e, "Failed to create suppression").into_response()
+        }
+    }
+}
+
+/// list_suppressions_handler
+/// Suppression listing handler
+pub async fn list_suppressions_handler(State(state): State<AppState>) -> impl IntoResponse {
+    match Suppression::list(&state.db_pool).await {
+        Ok(suppressions) => (StatusCode::OK, Json(suppressions)).into_response(),
+        Err(e) => {
+            eprintln!("Failed to list suppressions: {:?}", e);
+            ApiError::database(&e, "Failed to list suppressions").into_response()
+        }
+    }
+}
+
+/// delete_suppression_handler
+/// Suppression deletion handler
+/// Allows the address to be mailed again
+pub async fn delete_suppression_handler(
+    State(state): State<AppState>,
+    Path(email): Path<String>,
+) -> impl IntoResponse {
+    match Suppression::delete(&state.db_pool, &email).await {
+        Ok(true) => (StatusCode::OK, "OK").into_response(),
+        Ok(false) => ApiError::not_found("Suppression not found").into_response(),
+        Err(e) => {
+            eprintln!("Failed to delete suppression: {:?}", e);
+            ApiError::database(&e, "Failed to delete suppression").into_response()
+        }
+    }
+}
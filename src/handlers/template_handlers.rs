@@ -0,0 +1,172 @@
+use crate::errors::{ApiError, ApiJson};
+use crate::models::template::EmailTemplate;
+use crate::services::renderer::MessageTemplate;
+use crate::state::AppState;
+use crate::validation;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+
+/// CreateTemplateRequest
+/// Template creation request
+#[derive(Deserialize)]
+pub struct CreateTemplateRequest {
+    pub name: String,
+    pub subject: String,
+    pub content: String,
+}
+
+/// UpdateTemplateRequest
+/// Template update request, stored as a new version
+#[derive(Deserialize)]
+pub struct UpdateTemplateRequest {
+    pub name: Option<String>,
+    pub subject: String,
+    pub content: String,
+}
+
+/// RetrieveTemplateQueryParams
+/// Query parameters for retrieving a template
+#[derive(Deserialize)]
+pub struct RetrieveTemplateQueryParams {
+    pub version: Option<i32>,
+}
+
+/// validate_template
+/// Checks the required fields, size limits and template syntax
+fn validate_template(name: Option<&str>, subject: &str, content: &str) -> Result<(), ApiError> {
+    if name.is_some() {
+        validation::required("name", name)?;
+    }
+    validation::required("subject", Some(subject))?;
+    validation::required("content", Some(content))?;
+    validation::max_length("subject", subject, validation::MAX_SUBJECT_LENGTH)?;
+    validation::max_length("content", content, validation::MAX_CONTENT_LENGTH)?;
+    MessageTemplate::new(subject, content)
+        .map_err(|e| ApiError::bad_request("invalid_template", e))?;
+    Ok(())
+}
+
+/// create_template_handler
+/// Template creation handler
+/// Validates the template syntax and stores it as version 1
+pub async fn create_template_handler(
+    State(state): State<AppState>,
+    ApiJson(payload): ApiJson<CreateTemplateRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = validate_template(Some(&payload.name), &payload.subject, &payload.content) {
+        return e.into_response();
+    }
+    let template = EmailTemplate {
+        id: None,
+        name: payload.name,
+        version: 1,
+        subject: payload.subject,
+        content: payload.content,
+        created_at: None,
+    };
+    match template.save(&state.db_pool).await {
+        Ok(template) => (StatusCode::CREATED, Json(template)).into_response(),
+        Err(e) => {
+            eprintln!("Failed to create template: {:?}", e);
+            ApiError::database(&e, "Failed to create template").into_response()
+        }
+    }
+}
+
+/// list_templates_handler
+/// Template listing handler
+/// Returns the latest version of every template
+pub async fn list_templates_handler(State(state): State<AppState>) -> impl IntoResponse {
+    match EmailTemplate::list(&state.db_pool).await {
+        Ok(templates) => (StatusCode::OK, Json(templates)).into_response(),
+        Err(e) => {
+            eprintln!("Failed to list templates: {:?}", e);
+            ApiError::database(&e, "Failed to list templates").into_response()
+        }
+    }
+}
+
+/// retrieve_template_handler
+/// Template retrieval handler
+/// Returns the requested version, or the latest one if no version is given
+pub async fn retrieve_template_handler(
+    State(state): State<AppState>,
+    Path(template_id): Path<i32>,
+    Query(query): Query<RetrieveTemplateQueryParams>,
+) -> impl IntoResponse {
+    match EmailTemplate::find(&state.db_pool, template_id, query.version).await {
+        Ok(Some(template)) => (StatusCode::OK, Json(template)).into_response(),
+        Ok(None) => ApiError::not_found("Template not found").into_response(),
+        Err(e) => {
+            eprintln!("Failed to retrieve template: {:?}", e);
+            ApiError::database(&e, "Failed to retrieve template").into_response()
+        }
+    }
+}
+
+/// list_template_versions_handler
+/// Template version listing handler
+/// Returns every version of a template, newest first
+pub async fn list_template_versions_handler(
+    State(state): State<AppState>,
+    Path(template_id): Path<i32>,
+) -> impl IntoResponse {
+    match EmailTemplate::list_versions(&state.db_pool, template_id).await {
+        Ok(templates) if templates.is_empty() => {
+            ApiError::not_found("Template not found").into_response()
+        }
+        Ok(templates) => (StatusCode::OK, Json(templates)).into_response(),
+        Err(e) => {
+            eprintln!("Failed to list template versions: {:?}", e);
+            ApiError::database(&e, "Failed to list template versions").into_response()
+        }
+    }
+}
+
+/// update_template_handler
+/// Template update handler
+/// Stores the new subject/content as the next version; previous versions are kept
+pub async fn update_template_handler(
+    State(state): State<AppState>,
+    Path(template_id): Path<i32>,
+    ApiJson(payload): ApiJson<UpdateTemplateRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = validate_template(None, &payload.subject, &payload.content) {
+        return e.into_response();
+    }
+    match EmailTemplate::save_version(
+        &state.db_pool,
+        template_id,
+        payload.name,
+        &payload.subject,
+        &payload.content,
+    )
+    .await
+    {
+        Ok(Some(template)) => (StatusCode::OK, Json(template)).into_response(),
+        Ok(None) => ApiError::not_found("Template not found").into_response(),
+        Err(e) => {
+            eprintln!("Failed to update template: {:?}", e);
+            ApiError::database(&e, "Failed to update template").into_response()
+        }
+    }
+}
+
+/// delete_template_handler
+/// Template deletion handler
+pub async fn delete_template_handler(
+    State(state): State<AppState>,
+    Path(template_id): Path<i32>,
+) -> impl IntoResponse {
+    match EmailTemplate::delete(&state.db_pool, template_id).await {
+        Ok(true) => (StatusCode::OK, "OK").into_response(),
+        Ok(false) => ApiError::not_found("Template not found").into_response(),
+        Err(e) => {
+            eprintln!("Failed to delete template: {:?}", e);
+            ApiError::database(&e, "Failed to delete template").into_response()
+        }
+    }
+}
@@ -1,10 +1,105 @@
-use crate::models::request::EmailRequest;
+use crate::errors::{ApiError, ApiJson};
+use crate::handlers::message_handlers::RescheduleRequest;
+use crate::middlewares::auth_middlewares::Claims;
+use crate::models::audit_log::{AuditLog, AUDIT_ACTION_TOPIC_RESCHEDULE};
+use crate::models::request::{EmailMessageStatus, EmailRequest};
 use crate::models::result::EmailResult;
+use crate::models::topic::Topic;
 use crate::state::AppState;
-use axum::extract::{Path, State};
+use crate::validation;
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
-use axum::Json;
+use axum::{Extension, Json};
+use serde::Deserialize;
+
+/// CreateTopicRequest
+/// Topic registration request
+#[derive(Deserialize)]
+pub struct CreateTopicRequest {
+    pub topic_id: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// ListTopicsQueryParams
+/// Query parameters for listing topics
+/// `cursor` is the `next_cursor` of the previous page
+#[derive(Deserialize)]
+pub struct ListTopicsQueryParams {
+    pub cursor: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+/// create_topic_handler
+/// Topic registration handler
+/// Registers the topic, or updates the name, description and tags of an existing one
+pub async fn create_topic_handler(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ApiJson(payload): ApiJson<CreateTopicRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = validation::required("topic_id", Some(&payload.topic_id))
+        .and_then(|_| validation::max_length("topic_id", &payload.topic_id, 255))
+    {
+        return e.into_response();
+    }
+    if payload
+        .tags
+        .iter()
+        .any(|tag| tag.is_empty() || tag.contains(','))
+    {
+        return ApiError::bad_request("invalid_value", "tags must be non-empty and without commas")
+            .with_field("tags")
+            .into_response();
+    }
+    let topic = Topic {
+        topic_id: payload.topic_id,
+        name: payload.name,
+        description: payload.description,
+        tags: payload.tags,
+        created_by: Some(claims.sub),
+        ..Default::default()
+    };
+    match topic.save(&state.db_pool).await {
+        Ok(topic) => (StatusCode::OK, Json(topic)).into_response(),
+        Err(e) => {
+            eprintln!("Failed to save topic: {:?}", e);
+            ApiError::database(&e, "Failed to save topic").into_response()
+        }
+    }
+}
+
+/// list_topics_handler
+/// Topic listing handler
+/// Returns topics newest first with their request counts; `next_cursor` is null on the last page
+pub async fn list_topics_handler(
+    State(state): State<AppState>,
+    Query(query): Query<ListTopicsQueryParams>,
+) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    // One extra row tells whether another page follows
+    let mut topics = match Topic::list(&state.db_pool, query.cursor, limit + 1).await {
+        Ok(topics) => topics,
+        Err(e) => {
+            eprintln!("Failed to list topics: {:?}", e);
+            return ApiError::database(&e, "Failed to list topics").into_response();
+        }
+    };
+    let next_cursor = if topics.len() as i64 > limit {
+        topics.truncate(limit as usize);
+        topics.last().and_then(|summary| summary.topic.id)
+    } else {
+        None
+    };
+    let response = serde_json::json!({
+        "topics": topics,
+        "next_cursor": next_cursor,
+    });
+    (StatusCode::OK, Json(response)).into_response()
+}
 
 /// retrieve_topic_handler
 /// Topic retrieval handler
@@ -14,16 +109,63 @@ pub async fn retrieve_topic_handler(
     Path(topic_id): Path<String>,
 ) -> impl IntoResponse {
     if topic_id.is_empty() {
-        return (StatusCode::BAD_REQUEST, "topicId is required").into_response();
+        return ApiError::bad_request("required", "topicId is required")
+            .with_field("topic_id")
+            .into_response();
     }
     // Query request counts
     let request_counts =
-        EmailRequest::get_request_counts_by_topic_id(&state.db_pool, &topic_id).await;
+        match EmailRequest::get_request_counts_by_topic_id(&state.db_pool, &topic_id).await {
+            Ok(request_counts) => request_counts,
+            Err(e) => {
+                eprintln!("Failed to retrieve request counts: {:?}", e);
+                return ApiError::database(&e, "Failed to retrieve topic").into_response();
+            }
+        };
     // Query result counts
-    let result_counts = EmailResult::get_result_counts_by_topic_id(&state.db_pool, &topic_id).await;
+    let result_counts =
+        match EmailResult::get_result_counts_by_topic_id(&state.db_pool, &topic_id).await {
+            Ok(result_counts) => result_counts,
+            Err(e) => {
+                eprintln!("Failed to retrieve result counts: {:?}", e);
+                return ApiError::database(&e, "Failed to retrieve topic").into_response();
+            }
+        };
+    // Query click counts
+    let (total_clicks, unique_clicks) =
+        match EmailResult::get_click_counts_by_topic_id(&state.db_pool, &topic_id).await {
+            Ok(click_counts) => click_counts,
+            Err(e) => {
+                eprintln!("Failed to retrieve click counts: {:?}", e);
+                return ApiError::database(&e, "Failed to retrieve topic").into_response();
+            }
+        };
+    // Query bounce counts by type/sub type
+    let bounce_counts =
+        match EmailResult::get_bounce_counts_by_topic_id(&state.db_pool, &topic_id).await {
+            Ok(bounce_counts) => bounce_counts,
+            Err(e) => {
+                eprintln!("Failed to retrieve bounce counts: {:?}", e);
+                return ApiError::database(&e, "Failed to retrieve topic").into_response();
+            }
+        };
+    // Topics used before they were registered have no metadata
+    let topic = match Topic::find(&state.db_pool, &topic_id).await {
+        Ok(topic) => topic,
+        Err(e) => {
+            eprintln!("Failed to retrieve topic: {:?}", e);
+            return ApiError::database(&e, "Failed to retrieve topic").into_response();
+        }
+    };
     let response = serde_json::json!({
-        "request_counts": request_counts.expect("Failed to retrieve request counts"),
-        "result_counts": result_counts.expect("Failed to retrieve result counts"),
+        "topic": topic,
+        "request_counts": request_counts,
+        "result_counts": result_counts,
+        "bounce_counts": bounce_counts,
+        "click_counts": {
+            "total": total_clicks,
+            "unique": unique_clicks,
+        },
     });
     (StatusCode::OK, Json(response)).into_response()
 }
@@ -40,7 +182,137 @@ pub async fn stop_topic_handler(
         Ok(_) => (StatusCode::OK, "OK").into_response(),
         Err(e) => {
             eprintln!("Failed to stop topic: {:?}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to stop topic").into_response()
+            ApiError::database(&e, "Failed to stop topic").into_response()
+        }
+    }
+}
+
+/// pause_topic_handler
+/// Topic pause handler
+/// Holds the pending requests of the topic, including queued ones, until resumed
+/// Messages created for a paused topic are held as well
+pub async fn pause_topic_handler(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(topic_id): Path<String>,
+) -> impl IntoResponse {
+    match Topic::pause(&state.db_pool, &topic_id, Some(&claims.sub)).await {
+        Ok(Some(held)) => {
+            (StatusCode::OK, Json(serde_json::json!({ "paused": held }))).into_response()
         }
+        Ok(None) => ApiError::conflict("Topic is already paused").into_response(),
+        Err(e) => {
+            eprintln!("Failed to pause topic: {:?}", e);
+            ApiError::database(&e, "Failed to pause topic").into_response()
+        }
+    }
+}
+
+/// resume_topic_handler
+/// Topic resume handler
+/// Held requests are picked up by the scheduler at their scheduled time
+pub async fn resume_topic_handler(
+    State(state): State<AppState>,
+    Path(topic_id): Path<String>,
+) -> impl IntoResponse {
+    match Topic::resume(&state.db_pool, &topic_id).await {
+        Ok(Some(released)) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "resumed": released })),
+        )
+            .into_response(),
+        Ok(None) => ApiError::conflict("Topic is not paused").into_response(),
+        Err(e) => {
+            eprintln!("Failed to resume topic: {:?}", e);
+            ApiError::database(&e, "Failed to resume topic").into_response()
+        }
+    }
+}
+
+/// restart_topic_handler
+/// Stopped topic restart handler
+/// Stopped requests are sent again at their scheduled time, or held if the topic is paused
+pub async fn restart_topic_handler(
+    State(state): State<AppState>,
+    Path(topic_id): Path<String>,
+) -> impl IntoResponse {
+    let status = match Topic::is_paused(&state.db_pool, &topic_id).await {
+        Ok(true) => EmailMessageStatus::Paused,
+        Ok(false) => EmailMessageStatus::Created,
+        Err(e) => {
+            eprintln!("Failed to retrieve topic: {:?}", e);
+            return ApiError::database(&e, "Failed to restart topic").into_response();
+        }
+    };
+    match EmailRequest::restart_topic(&state.db_pool, &topic_id, status).await {
+        Ok(restarted) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "restarted": restarted })),
+        )
+            .into_response(),
+        Err(e) => {
+            eprintln!("Failed to restart topic: {:?}", e);
+            ApiError::database(&e, "Failed to restart topic").into_response()
+        }
+    }
+}
+
+/// reschedule_topic_handler
+/// Topic reschedule handler
+/// Moves every request of the topic still waiting to be sent (Created or Paused) to the new time
+pub async fn reschedule_topic_handler(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(topic_id): Path<String>,
+    ApiJson(payload): ApiJson<RescheduleRequest>,
+) -> impl IntoResponse {
+    let scheduled_at =
+        match validation::scheduled_at(Some(&payload.scheduled_at), payload.timezone.as_deref()) {
+            Ok(scheduled_at) => scheduled_at,
+            Err(e) => return e.into_response(),
+        };
+    match reschedule_topic(&state, &claims.sub, &topic_id, &scheduled_at).await {
+        Ok(rescheduled) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "rescheduled": rescheduled,
+                "scheduled_at": scheduled_at,
+            })),
+        )
+            .into_response(),
+        Err(e) => {
+            eprintln!("Failed to reschedule topic: {:?}", e);
+            ApiError::database(&e, "Failed to reschedule topic").into_response()
+        }
+    }
+}
+
+/// reschedule_topic
+/// Reschedules the waiting requests and records the change in one transaction
+/// Nothing is recorded when no request was waiting
+async fn reschedule_topic(
+    state: &AppState,
+    actor: &str,
+    topic_id: &str,
+    scheduled_at: &str,
+) -> Result<u64, sqlx::Error> {
+    let mut tx = state.db_pool.begin().await?;
+    let rescheduled = EmailRequest::reschedule_topic(&mut *tx, topic_id, scheduled_at).await?;
+    if rescheduled == 0 {
+        return Ok(0);
     }
+    AuditLog::new(
+        actor,
+        AUDIT_ACTION_TOPIC_RESCHEDULE,
+        "topic",
+        topic_id,
+        serde_json::json!({
+            "scheduled_at": scheduled_at,
+            "count": rescheduled,
+        }),
+    )
+    .save(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(rescheduled)
 }
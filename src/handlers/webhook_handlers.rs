@@ -0,0 +1,181 @@
+use crate::errors::{ApiError, ApiJson};
+use crate::models::ses_event::SesEventType;
+use crate::models::webhook::{
+    Webhook, WebhookDelivery, DELIVERY_STATUS_DELIVERED, DELIVERY_STATUS_FAILED,
+    DELIVERY_STATUS_PENDING,
+};
+use crate::services::webhook::generate_secret;
+use crate::state::AppState;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+use serde::Deserialize;
+
+/// CreateWebhookRequest
+/// Webhook registration request
+/// An empty `event_types` subscribes to every event; a missing `secret` is generated
+#[derive(Deserialize)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    pub secret: Option<String>,
+    pub topic_id: Option<String>,
+    pub event_types: Option<Vec<String>>,
+}
+
+/// ListDeliveriesQueryParams
+/// Query parameters for listing webhook deliveries
+#[derive(Deserialize)]
+pub struct ListDeliveriesQueryParams {
+    pub status: Option<String>,
+    pub webhook_id: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+/// normalize_event_types
+/// Maps event types to the stored names (e.g. "bounce" to "Bounce"); None if any is unknown
+fn normalize_event_types(event_types: Vec<String>) -> Option<Vec<String>> {
+    event_types
+        .iter()
+        .map(|name| SesEventType::from_name(name).map(|event_type| event_type.as_str().to_string()))
+        .collect()
+}
+
+/// is_http_url
+fn is_http_url(url: &str) -> bool {
+    reqwest::Url::parse(url)
+        .map(|url| url.scheme() == "http" || url.scheme() == "https")
+        .unwrap_or(false)
+}
+
+/// create_webhook_handler
+/// Webhook registration handler
+/// Returns the webhook with its signing secret, which is not shown again
+pub async fn create_webhook_handler(
+    State(state): State<AppState>,
+    ApiJson(payload): ApiJson<CreateWebhookRequest>,
+) -> impl IntoResponse {
+    if !is_http_url(&payload.url) {
+        return ApiError::bad_request("invalid_url", "url must be an http(s) URL")
+            .with_field("url")
+            .into_response();
+    }
+    let Some(event_types) = normalize_event_types(payload.event_types.unwrap_or_default()) else {
+        return ApiError::bad_request(
+            "invalid_value",
+            "event_types must be event types such as Bounce, Complaint, Delivery, Open or Click",
+        )
+        .with_field("event_types")
+        .into_response();
+    };
+    let secret = match payload.secret.filter(|secret| !secret.is_empty()) {
+        Some(secret) => secret,
+        None => match generate_secret() {
+            Ok(secret) => secret,
+            Err(e) => {
+                eprintln!("Failed to generate webhook secret: {:?}", e);
+                return ApiError::internal("Failed to create webhook").into_response();
+            }
+        },
+    };
+    let webhook = Webhook {
+        id: None,
+        url: payload.url,
+        secret,
+        topic_id: payload.topic_id.filter(|topic_id| !topic_id.is_empty()),
+        event_types,
+        last_result_id: 0,
+        created_at: None,
+    };
+    match webhook.save(&state.db_pool).await {
+        Ok(webhook) => {
+            let mut response = serde_json::json!(webhook);
+            response["secret"] = serde_json::json!(webhook.secret);
+            (StatusCode::CREATED, Json(response)).into_response()
+        }
+        Err(e) => {
+            eprintln!("Failed to create webhook: {:?}", e);
+            ApiError::database(&e, "Failed to create webhook").into_response()
+        }
+    }
+}
+
+/// list_webhooks_handler
+/// Webhook listing handler
+pub async fn list_webhooks_handler(State(state): State<AppState>) -> impl IntoResponse {
+    match Webhook::list(&state.db_pool).await {
+        Ok(webhooks) => (StatusCode::OK, Json(webhooks)).into_response(),
+        Err(e) => {
+            eprintln!("Failed to list webhooks: {:?}", e);
+            ApiError::database(&e, "Failed to list webhooks").into_response()
+        }
+    }
+}
+
+/// delete_webhook_handler
+/// Webhook deletion handler
+/// Pending deliveries of the webhook are no longer attempted
+pub async fn delete_webhook_handler(
+    State(state): State<AppState>,
+    Path(webhook_id): Path<i32>,
+) -> impl IntoResponse {
+    match Webhook::delete(&state.db_pool, webhook_id).await {
+        Ok(true) => (StatusCode::OK, "OK").into_response(),
+        Ok(false) => ApiError::not_found("Webhook not found").into_response(),
+        Err(e) => {
+            eprintln!("Failed to delete webhook: {:?}", e);
+            ApiError::database(&e, "Failed to delete webhook").into_response()
+        }
+    }
+}
+
+/// list_deliveries_handler
+/// Webhook delivery listing handler
+/// Defaults to failed deliveries (dead letters)
+pub async fn list_deliveries_handler(
+    State(state): State<AppState>,
+    Query(query): Query<ListDeliveriesQueryParams>,
+) -> impl IntoResponse {
+    let status = query
+        .status
+        .unwrap_or_else(|| DELIVERY_STATUS_FAILED.to_string());
+    if ![
+        DELIVERY_STATUS_PENDING,
+        DELIVERY_STATUS_DELIVERED,
+        DELIVERY_STATUS_FAILED,
+    ]
+    .contains(&status.as_str())
+    {
+        return ApiError::bad_request(
+            "invalid_value",
+            "status must be pending, delivered or failed",
+        )
+        .with_field("status")
+        .into_response();
+    }
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    match WebhookDelivery::list(&state.db_pool, &status, query.webhook_id, limit).await {
+        Ok(deliveries) => (StatusCode::OK, Json(deliveries)).into_response(),
+        Err(e) => {
+            eprintln!("Failed to list webhook deliveries: {:?}", e);
+            ApiError::database(&e, "Failed to list webhook deliveries").into_response()
+        }
+    }
+}
+
+/// retry_delivery_handler
+/// Webhook delivery retry handler
+/// Moves a failed delivery back to pending; it is sent on the dispatcher's next run
+pub async fn retry_delivery_handler(
+    State(state): State<AppState>,
+    Path(delivery_id): Path<i64>,
+) -> impl IntoResponse {
+    match WebhookDelivery::retry(&state.db_pool, delivery_id).await {
+        Ok(true) => (StatusCode::OK, "OK").into_response(),
+        Ok(false) => ApiError::not_found("Failed delivery not found").into_response(),
+        Err(e) => {
+            eprintln!("Failed to retry webhook delivery: {:?}", e);
+            ApiError::database(&e, "Failed to retry webhook delivery").into_response()
+        }
+    }
+}
@@ -1,22 +1,51 @@
 mod app;
 mod config;
+mod errors;
 mod handlers;
 mod middlewares;
 mod models;
 mod services;
 mod state;
 mod tests;
+mod validation;
 
+use services::events::{prune_orphan_events, prune_processed_events, reconcile_orphans};
+use services::monitor::{monitor_delivery_rates, SendingPause};
 use services::receiver::{receive_post_send_message, receive_send_message};
 use services::scheduler::schedule_pre_send_message;
+use services::sns::{HttpFetcher, ReqwestFetcher, SnsVerifier};
+use services::sqs::{poll_events, SqsQueue};
+use services::webhook::{dispatch_webhooks, WebhookDispatcher};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
 async fn main() -> Result<(), sqlx::Error> {
     let envs = config::get_environments();
 
+    // Tracking tokens signed with an empty key could be forged by anyone, and they go out in
+    // every email, so they must not share the key of the admin API
+    if envs.tracking_secret.is_empty() {
+        eprintln!("TRACKING_SECRET must be set");
+        std::process::exit(1);
+    }
+    if envs.tracking_secret == envs.jwt_secret {
+        eprintln!("TRACKING_SECRET must differ from JWT_SECRET");
+        std::process::exit(1);
+    }
+
+    // Email transport; fails on an unknown EMAIL_TRANSPORT before anything is sent
+    let transport = match services::sender::build_transport(envs).await {
+        Ok(transport) => transport,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
     // Sentry Initialization
     let sentry_dsn = &envs.sentry_dsn;
     let _guard = sentry::init((
@@ -34,7 +63,23 @@ async fn main() -> Result<(), sqlx::Error> {
         .await
         .expect("Failed to create pool");
 
+    // Re-queue claims left behind by a previous process; claims still leased may be
+    // in flight in another instance during a rolling deploy
+    let requeued = models::request::EmailRequest::requeue_expired(&db_pool).await?;
+    if requeued > 0 {
+        println!("Re-queued {} stale claims", requeued);
+    }
+
     // Initialize channels
+    let shutdown = CancellationToken::new();
+    // Restore a pause set before the restart before the scheduler starts claiming
+    let pause = SendingPause::load(&db_pool).await?;
+    if let Some(info) = pause.info() {
+        println!(
+            "Sending is paused since {}: {}",
+            info.paused_at, info.reason
+        );
+    }
     let (tx_send, rx_send) = tokio::sync::mpsc::channel(10000);
     let (tx_post_send, rx_post_send) = tokio::sync::mpsc::channel(1000);
     let cloned_tx_send = tx_send.clone();
@@ -42,23 +87,74 @@ async fn main() -> Result<(), sqlx::Error> {
     // Preprocess email sending
     tokio::spawn({
         let db_pool = db_pool.clone();
+        let pause = pause.clone();
+        let shutdown = shutdown.clone();
         async move {
-            schedule_pre_send_message(&tx_send, db_pool).await;
+            schedule_pre_send_message(&tx_send, db_pool, pause, shutdown).await;
         }
     });
 
+    // Bounce/complaint rate guard
+    tokio::spawn(monitor_delivery_rates(
+        db_pool.clone(),
+        pause.clone(),
+        shutdown.clone(),
+    ));
+
+    // Expire SNS dedup keys
+    tokio::spawn(prune_processed_events(
+        db_pool.clone(),
+        envs.event_dedup_retention_days,
+        shutdown.clone(),
+    ));
+
+    // Attach events that arrived before their message ID was written
+    tokio::spawn(reconcile_orphans(db_pool.clone(), shutdown.clone()));
+
+    // Drop orphan events that never matched, e.g. for mail sent by other systems
+    tokio::spawn(prune_orphan_events(
+        db_pool.clone(),
+        envs.orphan_event_retention_days,
+        shutdown.clone(),
+    ));
+
+    // Deliver results to registered webhooks
+    tokio::spawn(dispatch_webhooks(
+        WebhookDispatcher::from_environments(),
+        db_pool.clone(),
+        shutdown.clone(),
+    ));
+
+    // Pull SES events from SQS when the SNS endpoint cannot be exposed
+    if let Some(queue_url) = &envs.sqs_queue_url {
+        let queue = Arc::new(SqsQueue::new(envs, queue_url).await);
+        tokio::spawn(poll_events(queue, db_pool.clone(), shutdown.clone()));
+        println!("Polling SES events from {}", queue_url);
+    }
+
     // Email sending
     let arc_rx_send = Arc::new(Mutex::new(rx_send));
-    tokio::spawn({
+    let send_handle = tokio::spawn({
+        let db_pool = db_pool.clone();
+        let pause = pause.clone();
+        let shutdown = shutdown.clone();
         let cloned_arc_rx_send = Arc::clone(&arc_rx_send);
         async move {
-            receive_send_message(&cloned_arc_rx_send, &tx_post_send).await;
+            receive_send_message(
+                &cloned_arc_rx_send,
+                &tx_post_send,
+                transport,
+                db_pool,
+                pause,
+                shutdown,
+            )
+            .await;
         }
     });
 
     // Postprocess email sending
     let arc_rx_post_send = Arc::new(Mutex::new(rx_post_send));
-    tokio::spawn({
+    let post_send_handle = tokio::spawn({
         let cloned_arc_rx_post_send = Arc::clone(&arc_rx_post_send);
         let db_pool = db_pool.clone();
         async move {
@@ -66,7 +162,17 @@ async fn main() -> Result<(), sqlx::Error> {
         }
     });
 
-    let state = state::AppState::new(db_pool, cloned_tx_send);
+    let http_fetcher: Arc<dyn HttpFetcher> = Arc::new(ReqwestFetcher::default());
+    let mut state = state::AppState::new(db_pool, cloned_tx_send)
+        .with_shutdown(shutdown.clone())
+        .with_pause(pause)
+        .with_http_fetcher(Arc::clone(&http_fetcher));
+    if envs.sns_verify_signature {
+        state = state.with_sns_verifier(Arc::new(SnsVerifier::new(
+            http_fetcher,
+            envs.sns_cert_hosts.clone(),
+        )));
+    }
 
     // Initialize logger
     tracing_subscriber::registry()
@@ -84,6 +190,49 @@ async fn main() -> Result<(), sqlx::Error> {
     let port = &envs.server_port;
     let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
     println!("Server running on http://0.0.0.0:{}", port);
-    axum::serve(listener, app).await?;
+    tokio::spawn(shutdown_signal(
+        shutdown.clone(),
+        Duration::from_secs(envs.shutdown_timeout_seconds),
+    ));
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+        .await?;
+
+    // Wait for in-flight sends and pending result updates
+    let _ = send_handle.await;
+    let _ = post_send_handle.await;
+    println!("Shutdown complete");
     Ok(())
 }
+
+/// shutdown_signal
+/// Waits for SIGTERM/SIGINT and signals shutdown
+/// Exits the process if draining does not finish within the deadline;
+/// claims left behind are re-queued once their lease expires
+async fn shutdown_signal(shutdown: CancellationToken, deadline: Duration) {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    println!("Shutting down, draining for up to {:?}", deadline);
+    shutdown.cancel();
+
+    tokio::time::sleep(deadline).await;
+    eprintln!("Shutdown deadline exceeded, exiting");
+    std::process::exit(1);
+}
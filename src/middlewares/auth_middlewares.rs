@@ -1,6 +1,7 @@
+use crate::errors::ApiError;
 use axum::{
     body::Body,
-    http::{header::AUTHORIZATION, Request, StatusCode},
+    http::{header::AUTHORIZATION, Request},
     middleware::Next,
     response::IntoResponse,
 };
@@ -20,15 +21,15 @@ pub struct Claims {
 /// If a token exists, extract claims; if not, just move to the next step
 pub async fn jwt_auth_middleware(mut req: Request<Body>, next: Next) -> impl IntoResponse {
     let Some(auth_value) = req.headers().get(AUTHORIZATION) else {
-        return (StatusCode::UNAUTHORIZED, "No authorization header").into_response();
+        return ApiError::unauthorized("No authorization header").into_response();
     };
 
     let Ok(auth_str) = auth_value.to_str() else {
-        return (StatusCode::UNAUTHORIZED, "Invalid authorization header").into_response();
+        return ApiError::unauthorized("Invalid authorization header").into_response();
     };
 
     let Some(token) = auth_str.strip_prefix("Bearer ") else {
-        return (StatusCode::UNAUTHORIZED, "Invalid authorization header").into_response();
+        return ApiError::unauthorized("Invalid authorization header").into_response();
     };
 
     let envs = crate::config::get_environments();
@@ -38,7 +39,7 @@ pub async fn jwt_auth_middleware(mut req: Request<Body>, next: Next) -> impl Int
         &Validation::default(),
     ) {
         Ok(data) => data,
-        Err(_) => return (StatusCode::UNAUTHORIZED, "Invalid token").into_response(),
+        Err(_) => return ApiError::unauthorized("Invalid token").into_response(),
     };
 
     req.extensions_mut().insert(token_data.claims);
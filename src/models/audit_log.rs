@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{SqliteExecutor, SqlitePool};
+
+/// AUDIT_ACTION_MESSAGE_RESCHEDULE
+pub const AUDIT_ACTION_MESSAGE_RESCHEDULE: &str = "message.reschedule";
+
+/// AUDIT_ACTION_MESSAGE_CANCEL
+pub const AUDIT_ACTION_MESSAGE_CANCEL: &str = "message.cancel";
+
+/// AUDIT_ACTION_TOPIC_RESCHEDULE
+pub const AUDIT_ACTION_TOPIC_RESCHEDULE: &str = "topic.reschedule";
+
+/// AuditLog
+/// Change made through the API, with the JWT subject that made it
+/// `details` holds action specific values, e.g. the new scheduled time and the number of affected requests
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuditLog {
+    pub id: Option<i64>,
+    pub actor: String,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: String,
+    pub details: Value,
+    pub created_at: Option<String>,
+}
+
+impl AuditLog {
+    /// new
+    pub fn new(
+        actor: &str,
+        action: &str,
+        target_type: &str,
+        target_id: &str,
+        details: Value,
+    ) -> Self {
+        Self {
+            id: None,
+            actor: actor.to_string(),
+            action: action.to_string(),
+            target_type: target_type.to_string(),
+            target_id: target_id.to_string(),
+            details,
+            created_at: None,
+        }
+    }
+
+    /// save
+    /// Record the change; saved in the transaction of the change itself
+    pub async fn save<'e, E: SqliteExecutor<'e>>(self, executor: E) -> Result<Self, sqlx::Error> {
+        let details = self.details.to_string();
+        let row = sqlx::query!(
+            r#"
+            INSERT INTO audit_logs (actor, action, target_type, target_id, details, created_at)
+            VALUES (?, ?, ?, ?, ?, datetime('now'))
+            RETURNING id as "id!: i64", created_at as "created_at!: String"
+            "#,
+            self.actor,
+            self.action,
+            self.target_type,
+            self.target_id,
+            details,
+        )
+        .fetch_one(executor)
+        .await?;
+        Ok(Self {
+            id: Some(row.id),
+            created_at: Some(row.created_at),
+            ..self
+        })
+    }
+
+    /// list
+    /// Retrieve entries, newest first, optionally limited to one target
+    pub async fn list(
+        db_pool: &SqlitePool,
+        target_type: Option<&str>,
+        target_id: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id as "id!: i64",
+                   actor,
+                   action,
+                   target_type,
+                   target_id,
+                   details,
+                   created_at as "created_at!: String"
+            FROM audit_logs
+            WHERE (? IS NULL OR target_type = ?)
+            AND (? IS NULL OR target_id = ?)
+            ORDER BY id DESC
+            LIMIT ?
+            "#,
+            target_type,
+            target_type,
+            target_id,
+            target_id,
+            limit,
+        )
+        .fetch_all(db_pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| AuditLog {
+                id: Some(row.id),
+                actor: row.actor,
+                action: row.action,
+                target_type: row.target_type,
+                target_id: row.target_id,
+                details: row
+                    .details
+                    .and_then(|details| serde_json::from_str(&details).ok())
+                    .unwrap_or(Value::Null),
+                created_at: Some(row.created_at),
+            })
+            .collect())
+    }
+}
@@ -1,2 +1,11 @@
+pub mod audit_log;
+pub mod orphan_event;
+pub mod processed_event;
 pub mod request;
 pub mod result;
+pub mod ses_event;
+pub mod subscription;
+pub mod suppression;
+pub mod template;
+pub mod topic;
+pub mod webhook;
@@ -0,0 +1,153 @@
+use crate::models::ses_event::SesEvent;
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteExecutor, SqlitePool};
+
+/// OrphanEvent
+/// SES event whose message ID did not match any request when it arrived
+/// Kept until the reconciler can attach it to its request, or until the retention period ends
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OrphanEvent {
+    pub id: Option<i32>,
+    pub sns_message_id: String,
+    pub ses_message_id: String,
+    pub event_type: String,
+    pub raw: String,
+    pub created_at: Option<String>,
+}
+
+/// MatchedOrphanEvent
+/// Orphan event whose message ID now belongs to a request
+pub struct MatchedOrphanEvent {
+    pub id: i32,
+    pub request_id: i32,
+    pub raw: String,
+}
+
+impl OrphanEvent {
+    /// from_ses_event
+    /// Build an orphan event from a parsed SES event and its raw message
+    pub fn from_ses_event(
+        sns_message_id: &str,
+        ses_message_id: &str,
+        event: &SesEvent,
+        raw: String,
+    ) -> Self {
+        Self {
+            id: None,
+            sns_message_id: sns_message_id.to_string(),
+            ses_message_id: ses_message_id.to_string(),
+            event_type: event.event_type.as_str().to_string(),
+            raw,
+            created_at: None,
+        }
+    }
+
+    /// save
+    /// Save the unmatched event
+    pub async fn save<'e, E: SqliteExecutor<'e>>(self, executor: E) -> Result<Self, sqlx::Error> {
+        let instance = sqlx::query!(
+            r#"
+            INSERT INTO orphan_events (sns_message_id, ses_message_id, event_type, raw, created_at)
+            VALUES (?, ?, ?, ?, datetime('now'))
+            RETURNING id as "id: i32", created_at as "created_at: String"
+            "#,
+            self.sns_message_id,
+            self.ses_message_id,
+            self.event_type,
+            self.raw,
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(Self {
+            id: Some(instance.id),
+            created_at: Some(instance.created_at),
+            ..self
+        })
+    }
+
+    /// list
+    /// Retrieve unmatched events, oldest first
+    pub async fn list(db_pool: &SqlitePool, limit: i64) -> Result<Vec<Self>, sqlx::Error> {
+        let events = sqlx::query_as!(
+            OrphanEvent,
+            r#"
+            SELECT id as "id?: i32",
+                   sns_message_id,
+                   ses_message_id,
+                   event_type,
+                   raw,
+                   created_at as "created_at?: String"
+            FROM orphan_events
+            ORDER BY id
+            LIMIT ?
+            "#,
+            limit,
+        )
+        .fetch_all(db_pool)
+        .await?;
+        Ok(events)
+    }
+
+    /// count
+    /// Number of unmatched events
+    pub async fn count(db_pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+        let count = sqlx::query_scalar!(r#"SELECT COUNT(*) as "count: i64" FROM orphan_events"#)
+            .fetch_one(db_pool)
+            .await?;
+        Ok(count)
+    }
+
+    /// get_matched
+    /// Retrieve orphan events whose message ID has since been written to a request
+    pub async fn get_matched(
+        db_pool: &SqlitePool,
+        limit: i64,
+    ) -> Result<Vec<MatchedOrphanEvent>, sqlx::Error> {
+        let events = sqlx::query_as!(
+            MatchedOrphanEvent,
+            r#"
+            SELECT o.id as "id!: i32",
+                   r.id as "request_id!: i32",
+                   o.raw
+            FROM orphan_events o
+            JOIN email_requests r ON r.message_id = o.ses_message_id
+            ORDER BY o.id
+            LIMIT ?
+            "#,
+            limit,
+        )
+        .fetch_all(db_pool)
+        .await?;
+        Ok(events)
+    }
+
+    /// prune
+    /// Delete orphan events older than the retention period
+    /// Events for mail sent by other systems never match and would otherwise be kept forever
+    pub async fn prune(db_pool: &SqlitePool, retention_days: i64) -> Result<u64, sqlx::Error> {
+        let offset = format!("-{} days", retention_days);
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM orphan_events
+            WHERE created_at < datetime('now', ?)
+            "#,
+            offset,
+        )
+        .execute(db_pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// delete
+    /// Delete the orphan event once it has been attached to its request
+    pub async fn delete<'e, E: SqliteExecutor<'e>>(
+        executor: E,
+        id: i32,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!("DELETE FROM orphan_events WHERE id = ?", id)
+            .execute(executor)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+use sqlx::{SqliteExecutor, SqlitePool};
+
+/// ProcessedEvent
+/// SNS MessageId of a notification that has already been stored
+/// Used to skip notifications SNS (or SQS) delivers more than once
+pub struct ProcessedEvent;
+
+impl ProcessedEvent {
+    /// record
+    /// Record the message as processed
+    /// Returns false if it was already recorded
+    pub async fn record<'e, E: SqliteExecutor<'e>>(
+        executor: E,
+        message_id: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO processed_events (message_id, created_at)
+            VALUES (?, datetime('now'))
+            ON CONFLICT (message_id) DO NOTHING
+            "#,
+            message_id,
+        )
+        .execute(executor)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// prune
+    /// Delete keys older than the retention period
+    pub async fn prune(db_pool: &SqlitePool, retention_days: i64) -> Result<u64, sqlx::Error> {
+        let offset = format!("-{} days", retention_days);
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM processed_events
+            WHERE created_at < datetime('now', ?)
+            "#,
+            offset,
+        )
+        .execute(db_pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
@@ -1,19 +1,60 @@
-use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
-use serde::Deserialize;
-use sqlx::SqlitePool;
+use crate::models::result::{EmailResult, MessageEvent};
+use crate::models::topic::Topic;
+use chrono::{DateTime, Local, LocalResult, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteExecutor, SqlitePool};
 use std::convert::TryFrom;
 
 /// EmailMessageStatus
 /// Email message status
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum EmailMessageStatus {
-    Created = 0,   // Created
-    Processed = 1, // Processing
-    Sent = 2,      // Sent
-    Failed = 3,    // Failed
-    Stopped = 4,   // Stopped
+    Created = 0,    // Created
+    Processed = 1,  // Processing
+    Sent = 2,       // Sent
+    Failed = 3,     // Failed
+    Stopped = 4,    // Stopped
+    Suppressed = 5, // Recipient is on the suppression list
+    Paused = 6,     // Held while the topic is paused
 }
 
+impl EmailMessageStatus {
+    /// from_name
+    /// Status with the name, e.g. "Sent"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Created" => Some(Self::Created),
+            "Processed" => Some(Self::Processed),
+            "Sent" => Some(Self::Sent),
+            "Failed" => Some(Self::Failed),
+            "Stopped" => Some(Self::Stopped),
+            "Suppressed" => Some(Self::Suppressed),
+            "Paused" => Some(Self::Paused),
+            _ => None,
+        }
+    }
+
+    /// name
+    /// Name of a stored status value
+    pub fn name(status: i64) -> &'static str {
+        match status {
+            0 => "Created",
+            1 => "Processed",
+            2 => "Sent",
+            3 => "Failed",
+            4 => "Stopped",
+            5 => "Suppressed",
+            6 => "Paused",
+            _ => "Unknown",
+        }
+    }
+}
+
+/// CANCELLED_ERROR
+/// Error of requests cancelled one by one; restarting their topic does not bring them back
+pub const CANCELLED_ERROR: &str = "Cancelled";
+
 /// Request
 /// Email request
 #[derive(Deserialize, Clone)]
@@ -27,34 +68,163 @@ pub struct EmailRequest {
     pub status: i32,
     pub error: Option<String>,
     pub message_id: Option<String>,
+    pub leased_until: Option<String>,
+    /// Random token of the current claim; a stale copy of the request no longer matches it
+    pub lease_id: Option<String>,
+    pub template_id: Option<i32>,
+    pub template_version: Option<i32>,
+    pub track_clicks: bool,
+}
+
+/// MessageSummary
+/// Stored request without its content
+#[derive(Serialize, Debug)]
+pub struct MessageSummary {
+    pub id: i64,
+    pub topic_id: String,
+    pub email: String,
+    pub subject: String,
+    #[serde(serialize_with = "serialize_status")]
+    pub status: i64,
+    pub error: Option<String>,
+    pub message_id: Option<String>,
+    pub template_id: Option<i64>,
+    pub template_version: Option<i64>,
+    pub track_clicks: bool,
+    pub scheduled_at: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// serialize_status
+/// Statuses are returned by name, e.g. "Sent"
+fn serialize_status<S: serde::Serializer>(status: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(EmailMessageStatus::name(*status))
+}
+
+/// MessageDetail
+/// Stored request with its event timeline
+#[derive(Serialize, Debug)]
+pub struct MessageDetail {
+    #[serde(flatten)]
+    pub message: MessageSummary,
+    pub events: Vec<MessageEvent>,
+}
+
+/// MessageFilter
+/// Filters for listing requests; every field is optional
+/// Date ranges are UTC "YYYY-MM-DD HH:MM:SS", inclusive at the start and exclusive at the end
+#[derive(Default, Debug)]
+pub struct MessageFilter {
+    pub topic_id: Option<String>,
+    pub email: Option<String>,
+    pub status: Option<i32>,
+    pub created_from: Option<String>,
+    pub created_to: Option<String>,
+    pub scheduled_from: Option<String>,
+    pub scheduled_to: Option<String>,
+    /// Only requests with at least one result of this event type, e.g. "Bounce" or "Open"
+    pub has_event: Option<String>,
+}
+
+/// ScheduledAtError
+/// Reason a scheduled time cannot be converted to UTC
+#[derive(Debug, PartialEq, Eq)]
+pub enum ScheduledAtError {
+    /// Neither RFC 3339 nor "YYYY-MM-DD HH:MM:SS"
+    InvalidFormat,
+    /// Not an IANA time zone name, e.g. "Asia/Seoul"
+    InvalidTimeZone,
+    /// Skipped by a DST transition in the time zone
+    NonexistentLocalTime,
+    /// Repeated by a DST transition in the time zone
+    AmbiguousLocalTime,
+}
+
+/// convert_scheduled_at
+/// Converts a scheduled time to UTC ("YYYY-MM-DD HH:MM:SS")
+/// Accepts RFC 3339 with an offset, or a time without one in `timezone` (IANA name);
+/// without `timezone` the server's local time zone is used, as before
+/// A missing or empty value means now
+pub fn convert_scheduled_at(
+    scheduled_at: Option<&str>,
+    timezone: Option<&str>,
+) -> Result<String, ScheduledAtError> {
+    let scheduled_at = match scheduled_at {
+        Some(scheduled_at) if !scheduled_at.is_empty() => scheduled_at,
+        _ => return Ok(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()),
+    };
+    if let Ok(dt) = DateTime::parse_from_rfc3339(scheduled_at) {
+        return Ok(dt.naive_utc().format("%Y-%m-%d %H:%M:%S").to_string());
+    }
+    let naive_dt = NaiveDateTime::parse_from_str(scheduled_at, "%Y-%m-%d %H:%M:%S")
+        .map_err(|_| ScheduledAtError::InvalidFormat)?;
+    let utc_dt = match timezone.filter(|tz| !tz.is_empty()) {
+        Some(timezone) => {
+            let tz: Tz = timezone
+                .parse()
+                .map_err(|_| ScheduledAtError::InvalidTimeZone)?;
+            resolve_local(tz.from_local_datetime(&naive_dt))?.with_timezone(&Utc)
+        }
+        None => resolve_local(Local.from_local_datetime(&naive_dt))?.with_timezone(&Utc),
+    };
+    Ok(utc_dt.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+/// resolve_local
+/// Rejects local times skipped or repeated by a DST transition
+fn resolve_local<T: TimeZone>(
+    result: LocalResult<DateTime<T>>,
+) -> Result<DateTime<T>, ScheduledAtError> {
+    match result {
+        LocalResult::Single(dt) => Ok(dt),
+        LocalResult::None => Err(ScheduledAtError::NonexistentLocalTime),
+        LocalResult::Ambiguous(_, _) => Err(ScheduledAtError::AmbiguousLocalTime),
+    }
+}
+
+/// lease_expiry
+/// Returns the UTC time at which a lease taken now expires
+fn lease_expiry(lease_seconds: i64) -> String {
+    (Utc::now() + chrono::Duration::seconds(lease_seconds))
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
 }
 
 impl EmailRequest {
     /// save
     /// Save the email request
-    pub async fn save(self, db_pool: &SqlitePool) -> Self {
-        let now = Utc::now();
-        let scheduled_at = match self.scheduled_at.clone() {
-            Some(scheduled_at) => {
-                if scheduled_at.is_empty() {
-                    now.format("%Y-%m-%d %H:%M:%S").to_string()
-                } else {
-                    // Convert to UTC
-                    let naive_dt =
-                        NaiveDateTime::parse_from_str(&scheduled_at, "%Y-%m-%d %H:%M:%S")
-                            .expect("Failed to parse date");
-                    let local_dt: DateTime<Local> = Local
-                        .from_local_datetime(&naive_dt)
-                        .single()
-                        .expect("Ambiguous time or conversion failure");
-                    let utc_dt: DateTime<Utc> = local_dt.with_timezone(&Utc);
-                    utc_dt.format("%Y-%m-%d %H:%M:%S").to_string()
-                }
-            }
-            None => now.format("%Y-%m-%d %H:%M:%S").to_string(),
+    /// `scheduled_at` must already be in UTC (see `convert_scheduled_at`); requests without one are due now
+    /// Requests saved as Processed are claimed immediately with a lease of `lease_seconds`
+    /// Pending requests of a paused topic are saved as Paused instead, checked in the same statement
+    /// so a topic paused concurrently cannot let a new request through
+    /// A topic used for the first time is registered in the same transaction, owned by `created_by`
+    pub async fn save(
+        self,
+        db_pool: &SqlitePool,
+        lease_seconds: i64,
+        created_by: Option<&str>,
+    ) -> Result<Self, sqlx::Error> {
+        let scheduled_at = match self.scheduled_at.clone().filter(|s| !s.is_empty()) {
+            Some(scheduled_at) => scheduled_at,
+            None => Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
         };
+        let leased_until = if self.status == EmailMessageStatus::Processed as i32 {
+            Some(lease_expiry(lease_seconds))
+        } else {
+            None
+        };
+        let mut tx = db_pool.begin().await?;
+        if let Some(topic_id) = self.topic_id.as_deref().filter(|t| !t.is_empty()) {
+            Topic::ensure(&mut *tx, topic_id, created_by).await?;
+        }
         let instance = sqlx::query!(
             r#"
+            WITH hold AS (
+                SELECT ?6 IN (?12, ?13) AND EXISTS (
+                    SELECT 1 FROM topics WHERE topic_id = ?1 AND paused_at IS NOT NULL
+                ) AS paused
+            )
             INSERT INTO email_requests (
                 topic_id,
                 email,
@@ -62,10 +232,24 @@ impl EmailRequest {
                 content,
                 scheduled_at,
                 status,
+                error,
+                leased_until,
+                lease_id,
+                template_id,
+                template_version,
+                track_clicks,
                 created_at,
                 updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, datetime('now'), datetime('now'))
-            RETURNING id
+            )
+            SELECT
+                ?1, ?2, ?3, ?4, ?5,
+                CASE WHEN paused THEN ?14 ELSE ?6 END,
+                ?7,
+                CASE WHEN paused THEN NULL ELSE ?8 END,
+                CASE WHEN NOT paused AND ?8 IS NOT NULL THEN lower(hex(randomblob(16))) END,
+                ?9, ?10, ?11, datetime('now'), datetime('now')
+            FROM hold
+            RETURNING id, status, lease_id
             "#,
             self.topic_id,
             self.email,
@@ -73,26 +257,42 @@ impl EmailRequest {
             self.content,
             scheduled_at,
             self.status,
+            self.error,
+            leased_until,
+            self.template_id,
+            self.template_version,
+            self.track_clicks,
+            EmailMessageStatus::Created as i32,
+            EmailMessageStatus::Processed as i32,
+            EmailMessageStatus::Paused as i32,
         )
-        .fetch_one(db_pool)
-        .await
-        .expect("Failed to insert message");
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
 
-        EmailRequest {
+        Ok(EmailRequest {
             id: Some(instance.id as i32),
+            scheduled_at: Some(scheduled_at),
+            status: instance.status as i32,
+            // Held requests are saved without a lease
+            leased_until: leased_until.filter(|_| instance.lease_id.is_some()),
+            lease_id: instance.lease_id,
             ..self
-        }
+        })
     }
 
     /// update
     /// Update the email request status
-    pub async fn update(self, db_pool: &SqlitePool) {
+    /// Sends are recorded in the first/last sent times of the topic
+    pub async fn update(self, db_pool: &SqlitePool) -> Result<(), sqlx::Error> {
         sqlx::query!(
             r#"
             UPDATE email_requests
             SET status = ?,
                 message_id = ?,
                 error = ?,
+                leased_until = NULL,
+                lease_id = NULL,
                 updated_at = datetime('now')
             WHERE id = ?
             "#,
@@ -102,8 +302,144 @@ impl EmailRequest {
             self.id,
         )
         .execute(db_pool)
-        .await
-        .expect("Failed to update message status");
+        .await?;
+        if self.status == EmailMessageStatus::Sent as i32 {
+            if let Some(topic_id) = self.topic_id.as_deref().filter(|t| !t.is_empty()) {
+                Topic::mark_sent(db_pool, topic_id).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// claim_due
+    /// Claim up to `limit` requests whose scheduled time has passed
+    /// Claimed requests move to Processed and stay leased for `lease_seconds`
+    pub async fn claim_due(
+        db_pool: &SqlitePool,
+        limit: i64,
+        lease_seconds: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let leased_until = lease_expiry(lease_seconds);
+        let rows = sqlx::query!(
+            r#"
+            UPDATE email_requests
+            SET status = ?,
+                leased_until = ?,
+                lease_id = lower(hex(randomblob(16))),
+                updated_at = datetime('now')
+            WHERE id IN (
+                SELECT id
+                FROM email_requests
+                WHERE status = ? AND scheduled_at <= datetime('now')
+                ORDER BY scheduled_at
+                LIMIT ?
+            )
+            RETURNING id, topic_id, email, subject, content, template_id, template_version,
+                track_clicks as "track_clicks: bool", lease_id
+            "#,
+            EmailMessageStatus::Processed as i32,
+            leased_until,
+            EmailMessageStatus::Created as i32,
+            limit,
+        )
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| EmailRequest {
+                id: row.id.map(|id| id as i32),
+                topic_id: Some(row.topic_id),
+                email: row.email,
+                subject: row.subject,
+                content: row.content,
+                // Unused value (initialization only)
+                scheduled_at: None,
+                status: EmailMessageStatus::Processed as i32,
+                error: None,
+                message_id: None,
+                leased_until: Some(leased_until.clone()),
+                lease_id: row.lease_id,
+                template_id: row.template_id.map(|id| id as i32),
+                template_version: row.template_version.map(|version| version as i32),
+                track_clicks: row.track_clicks,
+            })
+            .collect())
+    }
+
+    /// confirm_lease
+    /// Extend the lease right before sending
+    /// The claim is identified by its `lease_id`, so a stale copy never matches a newer claim of the same request
+    /// Returns false if the claim is no longer held (expired and re-queued, stopped, paused topic, ...)
+    pub async fn confirm_lease(
+        &mut self,
+        db_pool: &SqlitePool,
+        lease_seconds: i64,
+    ) -> Result<bool, sqlx::Error> {
+        let leased_until = lease_expiry(lease_seconds);
+        let result = sqlx::query!(
+            r#"
+            UPDATE email_requests
+            SET leased_until = ?,
+                updated_at = datetime('now')
+            WHERE id = ? AND status = ? AND lease_id = ?
+            "#,
+            leased_until,
+            self.id,
+            EmailMessageStatus::Processed as i32,
+            self.lease_id,
+        )
+        .execute(db_pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        self.leased_until = Some(leased_until);
+        Ok(true)
+    }
+
+    /// release
+    /// Return a claimed request that was not sent to Created, if its claim is still held
+    pub async fn release(&self, db_pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE email_requests
+            SET status = ?,
+                leased_until = NULL,
+                lease_id = NULL,
+                updated_at = datetime('now')
+            WHERE id = ? AND status = ? AND lease_id = ?
+            "#,
+            EmailMessageStatus::Created as i32,
+            self.id,
+            EmailMessageStatus::Processed as i32,
+            self.lease_id,
+        )
+        .execute(db_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// requeue_expired
+    /// Return Processed requests whose lease has expired (or was never set) to Created
+    pub async fn requeue_expired(db_pool: &SqlitePool) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE email_requests
+            SET status = ?,
+                leased_until = NULL,
+                lease_id = NULL,
+                updated_at = datetime('now')
+            WHERE status = ?
+            AND (leased_until IS NULL OR leased_until < datetime('now'))
+            "#,
+            EmailMessageStatus::Created as i32,
+            EmailMessageStatus::Processed as i32,
+        )
+        .execute(db_pool)
+        .await?;
+        Ok(result.rows_affected())
     }
 
     /// sent_count
@@ -127,17 +463,18 @@ impl EmailRequest {
     }
 
     /// stop_topic
-    /// Stop sending requests for the topic
+    /// Stop sending requests for the topic, including held ones
     pub async fn stop_topic(db_pool: &SqlitePool, topic_id: &str) -> Result<(), sqlx::Error> {
         sqlx::query!(
             r#"
             UPDATE email_requests
             SET status = ?,
                 updated_at = datetime('now')
-            WHERE status = ? AND topic_id = ?
+            WHERE status IN (?, ?) AND topic_id = ?
             "#,
             EmailMessageStatus::Stopped as i32,
             EmailMessageStatus::Created as i32,
+            EmailMessageStatus::Paused as i32,
             topic_id,
         )
         .execute(db_pool)
@@ -145,6 +482,159 @@ impl EmailRequest {
         Ok(())
     }
 
+    /// hold_topic
+    /// Move the pending requests of the topic to Paused
+    /// Claimed requests lose their claim, so the receiver skips them if they are still queued
+    pub async fn hold_topic<'e, E: SqliteExecutor<'e>>(
+        executor: E,
+        topic_id: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE email_requests
+            SET status = ?,
+                leased_until = NULL,
+                lease_id = NULL,
+                updated_at = datetime('now')
+            WHERE status IN (?, ?) AND topic_id = ?
+            "#,
+            EmailMessageStatus::Paused as i32,
+            EmailMessageStatus::Created as i32,
+            EmailMessageStatus::Processed as i32,
+            topic_id,
+        )
+        .execute(executor)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// release_topic
+    /// Return the held requests of the topic to Created; due ones are picked up by the scheduler
+    pub async fn release_topic<'e, E: SqliteExecutor<'e>>(
+        executor: E,
+        topic_id: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE email_requests
+            SET status = ?,
+                updated_at = datetime('now')
+            WHERE status = ? AND topic_id = ?
+            "#,
+            EmailMessageStatus::Created as i32,
+            EmailMessageStatus::Paused as i32,
+            topic_id,
+        )
+        .execute(executor)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// restart_topic
+    /// Move the stopped requests of the topic back to `status` (Created, or Paused while the topic is paused)
+    /// Requests cancelled one by one stay stopped
+    pub async fn restart_topic(
+        db_pool: &SqlitePool,
+        topic_id: &str,
+        status: EmailMessageStatus,
+    ) -> Result<u64, sqlx::Error> {
+        let status = status as i32;
+        let result = sqlx::query!(
+            r#"
+            UPDATE email_requests
+            SET status = ?,
+                error = NULL,
+                updated_at = datetime('now')
+            WHERE status = ? AND topic_id = ? AND (error IS NULL OR error != ?)
+            "#,
+            status,
+            EmailMessageStatus::Stopped as i32,
+            topic_id,
+            CANCELLED_ERROR,
+        )
+        .execute(db_pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// reschedule
+    /// Move the scheduled time (UTC) of a request that is still waiting (Created or Paused)
+    /// Returns false if the request is no longer waiting
+    pub async fn reschedule<'e, E: SqliteExecutor<'e>>(
+        executor: E,
+        id: i32,
+        scheduled_at: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE email_requests
+            SET scheduled_at = ?,
+                updated_at = datetime('now')
+            WHERE id = ? AND status IN (?, ?)
+            "#,
+            scheduled_at,
+            id,
+            EmailMessageStatus::Created as i32,
+            EmailMessageStatus::Paused as i32,
+        )
+        .execute(executor)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// reschedule_topic
+    /// Move the scheduled time (UTC) of every waiting (Created or Paused) request of the topic
+    pub async fn reschedule_topic<'e, E: SqliteExecutor<'e>>(
+        executor: E,
+        topic_id: &str,
+        scheduled_at: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE email_requests
+            SET scheduled_at = ?,
+                updated_at = datetime('now')
+            WHERE topic_id = ? AND status IN (?, ?)
+            "#,
+            scheduled_at,
+            topic_id,
+            EmailMessageStatus::Created as i32,
+            EmailMessageStatus::Paused as i32,
+        )
+        .execute(executor)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// cancel
+    /// Stop a request waiting to be sent (Created or Paused)
+    /// Claimed (Processed) requests may already be in flight and are not cancelled
+    /// Returns false if the request was claimed, sent, failed or stopped
+    pub async fn cancel<'e, E: SqliteExecutor<'e>>(
+        executor: E,
+        id: i32,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE email_requests
+            SET status = ?,
+                error = ?,
+                leased_until = NULL,
+                lease_id = NULL,
+                updated_at = datetime('now')
+            WHERE id = ? AND status IN (?, ?)
+            "#,
+            EmailMessageStatus::Stopped as i32,
+            CANCELLED_ERROR,
+            id,
+            EmailMessageStatus::Created as i32,
+            EmailMessageStatus::Paused as i32,
+        )
+        .execute(executor)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
     /// get_request_counts_by_topic_id
     /// Retrieve request counts by topic
     pub async fn get_request_counts_by_topic_id(
@@ -165,19 +655,125 @@ impl EmailRequest {
 
         let mut request_counts = std::collections::HashMap::new();
         for r in requests {
-            let status = match r.status {
-                0 => "Created".to_string(),
-                1 => "Processed".to_string(),
-                2 => "Sent".to_string(),
-                3 => "Failed".to_string(),
-                4 => "Stopped".to_string(),
-                _ => "Unknown".to_string(),
-            };
+            let status = EmailMessageStatus::name(r.status).to_string();
             request_counts.insert(status, r.count.unwrap_or(0) as i32);
         }
         Ok(request_counts)
     }
 
+    /// find_detail
+    /// Retrieve a request by ID, or by SES message ID, with its event timeline
+    /// Returns None if no request matches
+    pub async fn find_detail(
+        db_pool: &SqlitePool,
+        id: Option<i32>,
+        message_id: Option<&str>,
+    ) -> Result<Option<MessageDetail>, sqlx::Error> {
+        let Some(message) = sqlx::query_as!(
+            MessageSummary,
+            r#"
+            SELECT id as "id!: i64",
+                   topic_id,
+                   email,
+                   subject,
+                   status,
+                   error,
+                   message_id,
+                   template_id,
+                   template_version,
+                   track_clicks,
+                   scheduled_at as "scheduled_at!: String",
+                   created_at as "created_at!: String",
+                   updated_at as "updated_at!: String"
+            FROM email_requests
+            WHERE (? IS NULL OR id = ?)
+            AND (? IS NULL OR message_id = ?)
+            AND deleted_at IS NULL
+            ORDER BY id
+            LIMIT 1
+            "#,
+            id,
+            id,
+            message_id,
+            message_id,
+        )
+        .fetch_optional(db_pool)
+        .await?
+        else {
+            return Ok(None);
+        };
+        let events = EmailResult::list_by_request_id(db_pool, message.id as i32).await?;
+        Ok(Some(MessageDetail { message, events }))
+    }
+
+    /// list
+    /// Retrieve requests matching the filter, newest first
+    /// Pages continue from `before_id`, the ID of the last request of the previous page
+    pub async fn list(
+        db_pool: &SqlitePool,
+        filter: &MessageFilter,
+        before_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<MessageSummary>, sqlx::Error> {
+        let messages = sqlx::query_as!(
+            MessageSummary,
+            r#"
+            SELECT id as "id!: i64",
+                   topic_id,
+                   email,
+                   subject,
+                   status,
+                   error,
+                   message_id,
+                   template_id,
+                   template_version,
+                   track_clicks,
+                   scheduled_at as "scheduled_at!: String",
+                   created_at as "created_at!: String",
+                   updated_at as "updated_at!: String"
+            FROM email_requests req
+            WHERE deleted_at IS NULL
+            AND (? IS NULL OR id < ?)
+            AND (? IS NULL OR topic_id = ?)
+            AND (? IS NULL OR email = ?)
+            AND (? IS NULL OR status = ?)
+            AND (? IS NULL OR created_at >= ?)
+            AND (? IS NULL OR created_at < ?)
+            AND (? IS NULL OR scheduled_at >= ?)
+            AND (? IS NULL OR scheduled_at < ?)
+            AND (? IS NULL OR EXISTS (
+                SELECT 1
+                FROM email_results res
+                WHERE res.request_id = req.id AND res.status = ?
+            ))
+            ORDER BY id DESC
+            LIMIT ?
+            "#,
+            before_id,
+            before_id,
+            filter.topic_id,
+            filter.topic_id,
+            filter.email,
+            filter.email,
+            filter.status,
+            filter.status,
+            filter.created_from,
+            filter.created_from,
+            filter.created_to,
+            filter.created_to,
+            filter.scheduled_from,
+            filter.scheduled_from,
+            filter.scheduled_to,
+            filter.scheduled_to,
+            filter.has_event,
+            filter.has_event,
+            limit,
+        )
+        .fetch_all(db_pool)
+        .await?;
+        Ok(messages)
+    }
+
     /// get_request_id_by_message_id
     /// Retrieve the request ID by message ID
     pub async fn get_request_id_by_message_id(
@@ -213,33 +809,10 @@ mod tests {
     use chrono::{Local, NaiveDateTime, Utc};
     use regex::Regex;
 
-    // production 코드에서 사용한 로직을 테스트하기 위해 동일한 변환 함수를 작성합니다.
-    // 이 함수는 Option<&str>으로 scheduled_at 값을 받아서, UTC 기준 "YYYY-MM-DD HH:MM:SS" 형식의 문자열을 반환합니다.
-    fn convert_scheduled_at(scheduled: Option<&str>) -> String {
-        let now = Utc::now();
-        match scheduled {
-            Some(s) => {
-                if s.is_empty() {
-                    now.format("%Y-%m-%d %H:%M:%S").to_string()
-                } else {
-                    let naive_dt = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
-                        .expect("Failed to parse date");
-                    let local_dt = Local
-                        .from_local_datetime(&naive_dt)
-                        .single()
-                        .expect("Ambiguous time or conversion failure");
-                    let utc_dt = local_dt.with_timezone(&Utc);
-                    utc_dt.format("%Y-%m-%d %H:%M:%S").to_string()
-                }
-            }
-            None => now.format("%Y-%m-%d %H:%M:%S").to_string(),
-        }
-    }
-
     // 테스트 1: scheduled_at 값이 None인 경우
     #[test]
     fn test_none_scheduled_at() {
-        let result = convert_scheduled_at(None);
+        let result = convert_scheduled_at(None, None).unwrap();
         // 결과 문자열이 "YYYY-MM-DD HH:MM:SS" 형식인지 확인
         let re = Regex::new(r"^\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}$").unwrap();
         assert!(
@@ -252,7 +825,7 @@ mod tests {
     // 테스트 1 변형: scheduled_at 값이 빈 문자열("")인 경우 -> None과 동일하게 처리됨
     #[test]
     fn test_empty_scheduled_at() {
-        let result = convert_scheduled_at(Some(""));
+        let result = convert_scheduled_at(Some(""), None).unwrap();
         let re = Regex::new(r"^\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}$").unwrap();
         assert!(
             re.is_match(&result),
@@ -263,23 +836,27 @@ mod tests {
 
     // 테스트 2: scheduled_at 값이 있으나 형식이 전혀 맞지 않는 경우
     #[test]
-    #[should_panic(expected = "Failed to parse date")]
     fn test_invalid_format_completely_different() {
-        let _ = convert_scheduled_at(Some("invalid_date_string"));
+        assert_eq!(
+            convert_scheduled_at(Some("invalid_date_string"), None),
+            Err(ScheduledAtError::InvalidFormat)
+        );
     }
 
     // 테스트 2 변형: 초(second) 정보가 빠진 경우 (형식 불일치)
     #[test]
-    #[should_panic(expected = "Failed to parse date")]
     fn test_invalid_format_missing_seconds() {
-        let _ = convert_scheduled_at(Some("2023-10-12 15:30"));
+        assert_eq!(
+            convert_scheduled_at(Some("2023-10-12 15:30"), None),
+            Err(ScheduledAtError::InvalidFormat)
+        );
     }
 
     // 테스트 3: 입력 값이 정상적으로 들어왔을 때
     #[test]
     fn test_valid_scheduled_at() {
         let input = "2023-10-12 15:30:45";
-        let result = convert_scheduled_at(Some(input));
+        let result = convert_scheduled_at(Some(input), None).unwrap();
         // 예상 결과를 직접 계산: 입력값을 로컬 시간으로 해석한 후 UTC로 변환합니다.
         let naive = NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M:%S").unwrap();
         let local_dt = Local.from_local_datetime(&naive).single().unwrap();
@@ -295,7 +872,7 @@ mod tests {
     #[test]
     fn test_timezone_conversion() {
         let input = "2023-10-12 15:30:45";
-        let result = convert_scheduled_at(Some(input));
+        let result = convert_scheduled_at(Some(input), None).unwrap();
         // 로컬 날짜시간과 UTC 변환 값을 계산합니다.
         let naive = NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M:%S").unwrap();
         let local_dt = Local.from_local_datetime(&naive).single().unwrap();
@@ -319,7 +896,7 @@ mod tests {
     #[test]
     fn test_future_date_format() {
         let input = "2099-12-31 23:59:59";
-        let result = convert_scheduled_at(Some(input));
+        let result = convert_scheduled_at(Some(input), None).unwrap();
         let re = Regex::new(r"^\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}$").unwrap();
         assert!(
             re.is_match(&result),
@@ -327,4 +904,138 @@ mod tests {
             result
         );
     }
+
+    // 테스트 5: 오프셋이 포함된 RFC 3339 값은 서버 타임존과 무관하게 변환되는지 확인
+    #[test]
+    fn test_rfc3339_scheduled_at() {
+        assert_eq!(
+            convert_scheduled_at(Some("2024-01-01T09:00:00+09:00"), None).unwrap(),
+            "2024-01-01 00:00:00"
+        );
+        assert_eq!(
+            convert_scheduled_at(Some("2024-01-01T09:00:00Z"), Some("Asia/Seoul")).unwrap(),
+            "2024-01-01 09:00:00"
+        );
+    }
+
+    // 테스트 6: IANA 타임존이 지정되면 해당 타임존의 시간으로 해석되는지 확인
+    #[test]
+    fn test_iana_timezone_scheduled_at() {
+        assert_eq!(
+            convert_scheduled_at(Some("2024-01-01 09:00:00"), Some("Asia/Seoul")).unwrap(),
+            "2024-01-01 00:00:00"
+        );
+        assert_eq!(
+            convert_scheduled_at(Some("2024-07-01 09:00:00"), Some("America/New_York")).unwrap(),
+            "2024-07-01 13:00:00"
+        );
+        assert_eq!(
+            convert_scheduled_at(Some("2024-01-01 09:00:00"), Some("Mars/Olympus")),
+            Err(ScheduledAtError::InvalidTimeZone)
+        );
+    }
+
+    // 테스트 7: 서머타임 전환으로 건너뛰거나 반복되는 시간은 거부되는지 확인
+    #[test]
+    fn test_dst_transition_scheduled_at() {
+        assert_eq!(
+            convert_scheduled_at(Some("2024-03-10 02:30:00"), Some("America/New_York")),
+            Err(ScheduledAtError::NonexistentLocalTime)
+        );
+        assert_eq!(
+            convert_scheduled_at(Some("2024-11-03 01:30:00"), Some("America/New_York")),
+            Err(ScheduledAtError::AmbiguousLocalTime)
+        );
+    }
+
+    // 테스트 8: 예약 시간이 지난 요청만 선점되고, 선점 시간이 만료된 요청만 다시 대기열에 들어가며, 이전 선점은 새 선점과 구분되는지 확인
+    #[tokio::test]
+    async fn test_claim_due_and_requeue_expired() {
+        let db_pool = crate::tests::db_pool().await;
+        sqlx::query(
+            r#"
+            INSERT INTO email_requests (topic_id, email, subject, content, scheduled_at)
+            VALUES ('t', 'due@example.com', 's', 'c', datetime('now', '-1 minutes')),
+                   ('t', 'later@example.com', 's', 'c', datetime('now', '+1 hours'));
+            "#,
+        )
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        let mut claimed = EmailRequest::claim_due(&db_pool, 10, 60).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].email, "due@example.com");
+        let mut stale = claimed.pop().unwrap();
+        assert!(EmailRequest::claim_due(&db_pool, 10, 60)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // A claim still leased may be in flight in another instance
+        assert_eq!(EmailRequest::requeue_expired(&db_pool).await.unwrap(), 0);
+        sqlx::query("UPDATE email_requests SET leased_until = datetime('now', '-1 minutes')")
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(EmailRequest::requeue_expired(&db_pool).await.unwrap(), 1);
+        let mut claimed = EmailRequest::claim_due(&db_pool, 10, 60).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+
+        // The stale copy does not hold the new claim, even with the same lease expiry
+        stale.leased_until = claimed[0].leased_until.clone();
+        assert!(!stale.confirm_lease(&db_pool, 60).await.unwrap());
+        assert!(claimed[0].confirm_lease(&db_pool, 60).await.unwrap());
+    }
+
+    // 테스트 9: 일시 정지된 토픽의 요청은 저장 시점에 선점 없이 Paused로 저장되는지 확인
+    #[tokio::test]
+    async fn test_save_holds_requests_of_paused_topic() {
+        let db_pool = crate::tests::db_pool().await;
+        sqlx::query(
+            r#"
+            INSERT INTO topics (topic_id, paused_at, created_at, updated_at)
+            VALUES ('paused', datetime('now'), datetime('now'), datetime('now'));
+            "#,
+        )
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        let request = |topic_id: &str, status: EmailMessageStatus| EmailRequest {
+            id: None,
+            topic_id: Some(topic_id.to_string()),
+            email: "test@example.com".to_string(),
+            subject: "s".to_string(),
+            content: "c".to_string(),
+            scheduled_at: None,
+            status: status as i32,
+            error: None,
+            message_id: None,
+            leased_until: None,
+            lease_id: None,
+            template_id: None,
+            template_version: None,
+            track_clicks: true,
+        };
+        let held = request("paused", EmailMessageStatus::Processed)
+            .save(&db_pool, 60, None)
+            .await
+            .unwrap();
+        assert_eq!(held.status, EmailMessageStatus::Paused as i32);
+        assert!(held.leased_until.is_none() && held.lease_id.is_none());
+
+        let failed = request("paused", EmailMessageStatus::Failed)
+            .save(&db_pool, 60, None)
+            .await
+            .unwrap();
+        assert_eq!(failed.status, EmailMessageStatus::Failed as i32);
+
+        let queued = request("active", EmailMessageStatus::Processed)
+            .save(&db_pool, 60, None)
+            .await
+            .unwrap();
+        assert_eq!(queued.status, EmailMessageStatus::Processed as i32);
+        assert!(queued.leased_until.is_some() && queued.lease_id.is_some());
+    }
 }
@@ -1,35 +1,116 @@
+use crate::models::ses_event::SesEvent;
 use serde::{Deserialize, Serialize};
-use sqlx::SqlitePool;
+use sqlx::{SqliteExecutor, SqlitePool};
+
+/// DeliveryStats
+/// Sent, permanently bounced and complained request counts of a topic
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct DeliveryStats {
+    pub topic_id: String,
+    pub sent: i64,
+    pub bounces: i64,
+    pub complaints: i64,
+}
 
 /// Result
 /// Email delivery result
-#[derive(Serialize, Deserialize)]
+/// Key fields of SES events are stored in their own columns next to `raw`
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
 pub struct EmailResult {
     pub id: Option<i32>,
     pub request_id: i32,
     pub status: String,
     pub raw: Option<String>,
+    pub bounce_type: Option<String>,
+    pub bounce_sub_type: Option<String>,
+    pub complaint_feedback_type: Option<String>,
+    pub diagnostic_code: Option<String>,
+    pub reject_reason: Option<String>,
+    pub delay_type: Option<String>,
+    pub link: Option<String>,
+    pub event_at: Option<String>,
+}
+
+/// MessageEvent
+/// Event in the timeline of a request, with the key fields parsed from the SES notification
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MessageEvent {
+    pub id: i64,
+    pub event_type: String,
+    pub bounce_type: Option<String>,
+    pub bounce_sub_type: Option<String>,
+    pub complaint_feedback_type: Option<String>,
+    pub diagnostic_code: Option<String>,
+    pub reject_reason: Option<String>,
+    pub delay_type: Option<String>,
+    pub link: Option<String>,
+    pub event_at: Option<String>,
+    pub created_at: String,
 }
 
 impl EmailResult {
+    /// from_ses_event
+    /// Build the result of an SES event for the request, keeping the original message in `raw`
+    pub fn from_ses_event(request_id: i32, event: &SesEvent, raw: String) -> Self {
+        Self {
+            id: None,
+            request_id,
+            status: event.event_type.as_str().to_string(),
+            raw: Some(raw),
+            bounce_type: event.bounce.as_ref().map(|b| b.bounce_type.clone()),
+            bounce_sub_type: event
+                .bounce
+                .as_ref()
+                .and_then(|b| b.bounce_sub_type.clone()),
+            complaint_feedback_type: event
+                .complaint
+                .as_ref()
+                .and_then(|c| c.complaint_feedback_type.clone()),
+            diagnostic_code: event.diagnostic_code().map(String::from),
+            reject_reason: event.reject.as_ref().and_then(|r| r.reason.clone()),
+            delay_type: event
+                .delivery_delay
+                .as_ref()
+                .and_then(|d| d.delay_type.clone()),
+            link: event.click.as_ref().and_then(|c| c.link.clone()),
+            event_at: event.timestamp().map(String::from),
+        }
+    }
+
     /// save
     /// Save email delivery result
-    pub async fn save(self, db_pool: &SqlitePool) -> Result<Self, sqlx::Error> {
+    pub async fn save<'e, E: SqliteExecutor<'e>>(self, executor: E) -> Result<Self, sqlx::Error> {
         let instance = sqlx::query!(
             r#"
             INSERT INTO email_results (
                 request_id,
                 status,
                 raw,
+                bounce_type,
+                bounce_sub_type,
+                complaint_feedback_type,
+                diagnostic_code,
+                reject_reason,
+                delay_type,
+                link,
+                event_at,
                 created_at
-            ) VALUES (?, ?, ?, datetime('now'))
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, datetime('now'))
             RETURNING id
             "#,
             self.request_id,
             self.status,
             self.raw,
+            self.bounce_type,
+            self.bounce_sub_type,
+            self.complaint_feedback_type,
+            self.diagnostic_code,
+            self.reject_reason,
+            self.delay_type,
+            self.link,
+            self.event_at,
         )
-        .fetch_one(db_pool)
+        .fetch_one(executor)
         .await?;
 
         Ok(Self {
@@ -38,6 +119,38 @@ impl EmailResult {
         })
     }
 
+    /// list_by_request_id
+    /// Retrieve the events of a request in the order they happened
+    /// SES timestamps are used when present; tracked opens and clicks only have the time they were saved
+    pub async fn list_by_request_id(
+        db_pool: &SqlitePool,
+        request_id: i32,
+    ) -> Result<Vec<MessageEvent>, sqlx::Error> {
+        let events = sqlx::query_as!(
+            MessageEvent,
+            r#"
+            SELECT id as "id!: i64",
+                   status as event_type,
+                   bounce_type,
+                   bounce_sub_type,
+                   complaint_feedback_type,
+                   diagnostic_code,
+                   reject_reason,
+                   delay_type,
+                   link,
+                   event_at,
+                   created_at as "created_at!: String"
+            FROM email_results
+            WHERE request_id = ?
+            ORDER BY COALESCE(datetime(event_at), created_at), id
+            "#,
+            request_id,
+        )
+        .fetch_all(db_pool)
+        .await?;
+        Ok(events)
+    }
+
     /// get_result_counts_by_topic_id
     /// Retrieve result counts by topic
     pub async fn get_result_counts_by_topic_id(
@@ -46,7 +159,7 @@ impl EmailResult {
     ) -> Result<std::collections::HashMap<String, i32>, sqlx::Error> {
         let results = sqlx::query!(
             r#"
-            SELECT status, COUNT(DISTINCT request_id) as count
+            SELECT status, COUNT(DISTINCT request_id) as "count!: i64"
             FROM email_results
             WHERE request_id IN (
                 SELECT id
@@ -66,6 +179,94 @@ impl EmailResult {
         }
         Ok(result_counts)
     }
+
+    /// get_click_counts_by_topic_id
+    /// Retrieve total and unique (per request) click counts by topic
+    pub async fn get_click_counts_by_topic_id(
+        db_pool: &SqlitePool,
+        topic_id: &str,
+    ) -> Result<(i32, i32), sqlx::Error> {
+        let counts = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as total, COUNT(DISTINCT request_id) as "unique_count: i64"
+            FROM email_results
+            WHERE status = 'Click'
+            AND request_id IN (
+                SELECT id
+                FROM email_requests
+                WHERE topic_id = ?
+            )
+            "#,
+            topic_id,
+        )
+        .fetch_one(db_pool)
+        .await?;
+        Ok((counts.total as i32, counts.unique_count as i32))
+    }
+
+    /// get_bounce_counts_by_topic_id
+    /// Retrieve bounced request counts by topic, keyed by "bounceType/bounceSubType"
+    pub async fn get_bounce_counts_by_topic_id(
+        db_pool: &SqlitePool,
+        topic_id: &str,
+    ) -> Result<std::collections::HashMap<String, i32>, sqlx::Error> {
+        let results = sqlx::query!(
+            r#"
+            SELECT COALESCE(bounce_type, 'Unknown') || '/' || COALESCE(bounce_sub_type, 'Unknown') as "kind!: String",
+                   COUNT(DISTINCT request_id) as "count!: i64"
+            FROM email_results
+            WHERE status = 'Bounce'
+            AND request_id IN (
+                SELECT id
+                FROM email_requests
+                WHERE topic_id = ?
+            )
+            GROUP BY 1
+            "#,
+            topic_id,
+        )
+        .fetch_all(db_pool)
+        .await?;
+
+        let mut bounce_counts = std::collections::HashMap::new();
+        for result in results {
+            bounce_counts.insert(result.kind, result.count as i32);
+        }
+        Ok(bounce_counts)
+    }
+
+    /// get_delivery_stats_by_topic
+    /// Retrieve, per topic, the requests sent in the last `window_minutes`
+    /// and how many of them permanently bounced or complained
+    pub async fn get_delivery_stats_by_topic(
+        db_pool: &SqlitePool,
+        window_minutes: i64,
+    ) -> Result<Vec<DeliveryStats>, sqlx::Error> {
+        let window = format!("-{} minutes", window_minutes);
+        let stats = sqlx::query_as!(
+            DeliveryStats,
+            r#"
+            SELECT r.topic_id,
+                   COUNT(*) as "sent!: i64",
+                   SUM(EXISTS (
+                       SELECT 1 FROM email_results e
+                       WHERE e.request_id = r.id AND e.status = 'Bounce'
+                       AND e.bounce_type = 'Permanent'
+                   )) as "bounces!: i64",
+                   SUM(EXISTS (
+                       SELECT 1 FROM email_results e
+                       WHERE e.request_id = r.id AND e.status = 'Complaint'
+                   )) as "complaints!: i64"
+            FROM email_requests r
+            WHERE r.status = 2 AND r.updated_at >= datetime('now', ?)
+            GROUP BY r.topic_id
+            "#,
+            window,
+        )
+        .fetch_all(db_pool)
+        .await?;
+        Ok(stats)
+    }
 }
 
 #[cfg(test)]
@@ -110,6 +311,14 @@ mod tests {
             request_id INTEGER NOT NULL,
             status VARCHAR(50) NOT NULL,
             raw TEXT,
+            bounce_type VARCHAR(50) DEFAULT NULL,
+            bounce_sub_type VARCHAR(50) DEFAULT NULL,
+            complaint_feedback_type VARCHAR(50) DEFAULT NULL,
+            diagnostic_code TEXT DEFAULT NULL,
+            reject_reason VARCHAR(255) DEFAULT NULL,
+            delay_type VARCHAR(50) DEFAULT NULL,
+            link TEXT DEFAULT NULL,
+            event_at VARCHAR(50) DEFAULT NULL,
             created_at DATETIME NOT NULL DEFAULT (datetime('now')),
             FOREIGN KEY (request_id) REFERENCES email_requests(id)
         );
@@ -148,6 +357,7 @@ mod tests {
             request_id: 1,
             status: "success".to_string(),
             raw: Some("raw data 1".to_string()),
+            ..Default::default()
         }
         .save(&db_pool)
         .await
@@ -158,6 +368,7 @@ mod tests {
             request_id: 1,
             status: "failed".to_string(),
             raw: Some("raw data 2".to_string()),
+            ..Default::default()
         }
         .save(&db_pool)
         .await
@@ -0,0 +1,363 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// SesEventType
+/// Type of an SES notification (`notificationType`) or published event (`eventType`)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
+pub enum SesEventType {
+    Send,
+    Delivery,
+    Bounce,
+    Complaint,
+    Reject,
+    DeliveryDelay,
+    RenderingFailure,
+    Subscription,
+    Open,
+    Click,
+    Other(String),
+}
+
+impl From<String> for SesEventType {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "Send" => SesEventType::Send,
+            "Delivery" => SesEventType::Delivery,
+            "Bounce" => SesEventType::Bounce,
+            "Complaint" => SesEventType::Complaint,
+            "Reject" => SesEventType::Reject,
+            "DeliveryDelay" => SesEventType::DeliveryDelay,
+            "Rendering Failure" | "RenderingFailure" => SesEventType::RenderingFailure,
+            "Subscription" => SesEventType::Subscription,
+            "Open" => SesEventType::Open,
+            "Click" => SesEventType::Click,
+            _ => SesEventType::Other(value),
+        }
+    }
+}
+
+impl From<SesEventType> for String {
+    fn from(value: SesEventType) -> Self {
+        value.as_str().to_string()
+    }
+}
+
+impl SesEventType {
+    /// from_name
+    /// Parses a known event type case-insensitively, accepting aliases such as "bounced" or "opened"
+    /// Returns None for unknown types
+    pub fn from_name(name: &str) -> Option<Self> {
+        let event_type = match name.trim().to_ascii_lowercase().as_str() {
+            "send" | "sent" => SesEventType::Send,
+            "delivery" | "delivered" => SesEventType::Delivery,
+            "bounce" | "bounced" => SesEventType::Bounce,
+            "complaint" | "complained" => SesEventType::Complaint,
+            "reject" | "rejected" => SesEventType::Reject,
+            "deliverydelay" | "delayed" => SesEventType::DeliveryDelay,
+            "renderingfailure" | "rendering failure" => SesEventType::RenderingFailure,
+            "subscription" => SesEventType::Subscription,
+            "open" | "opened" => SesEventType::Open,
+            "click" | "clicked" => SesEventType::Click,
+            _ => return None,
+        };
+        Some(event_type)
+    }
+
+    /// as_str
+    /// Name stored as the result status
+    pub fn as_str(&self) -> &str {
+        match self {
+            SesEventType::Send => "Send",
+            SesEventType::Delivery => "Delivery",
+            SesEventType::Bounce => "Bounce",
+            SesEventType::Complaint => "Complaint",
+            SesEventType::Reject => "Reject",
+            SesEventType::DeliveryDelay => "DeliveryDelay",
+            SesEventType::RenderingFailure => "RenderingFailure",
+            SesEventType::Subscription => "Subscription",
+            SesEventType::Open => "Open",
+            SesEventType::Click => "Click",
+            SesEventType::Other(value) => value,
+        }
+    }
+}
+
+/// SesMail
+/// Original message the event refers to
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SesMail {
+    pub message_id: String,
+    pub timestamp: Option<String>,
+    pub source: Option<String>,
+    #[serde(default)]
+    pub destination: Vec<String>,
+    pub tags: Option<Value>,
+}
+
+/// SesRecipient
+/// Recipient affected by a bounce, complaint or delivery delay
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SesRecipient {
+    pub email_address: String,
+    pub action: Option<String>,
+    pub status: Option<String>,
+    pub diagnostic_code: Option<String>,
+}
+
+/// SesBounce
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SesBounce {
+    pub bounce_type: String,
+    pub bounce_sub_type: Option<String>,
+    #[serde(default)]
+    pub bounced_recipients: Vec<SesRecipient>,
+    pub timestamp: Option<String>,
+    pub feedback_id: Option<String>,
+    #[serde(rename = "reportingMTA")]
+    pub reporting_mta: Option<String>,
+}
+
+/// SesComplaint
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SesComplaint {
+    #[serde(default)]
+    pub complained_recipients: Vec<SesRecipient>,
+    pub timestamp: Option<String>,
+    pub feedback_id: Option<String>,
+    pub complaint_feedback_type: Option<String>,
+    pub complaint_sub_type: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+/// SesDelivery
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SesDelivery {
+    pub timestamp: Option<String>,
+    pub processing_time_millis: Option<i64>,
+    #[serde(default)]
+    pub recipients: Vec<String>,
+    pub smtp_response: Option<String>,
+    #[serde(rename = "reportingMTA")]
+    pub reporting_mta: Option<String>,
+}
+
+/// SesReject
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SesReject {
+    pub reason: Option<String>,
+}
+
+/// SesDeliveryDelay
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SesDeliveryDelay {
+    pub timestamp: Option<String>,
+    pub delay_type: Option<String>,
+    pub expiration_time: Option<String>,
+    #[serde(default)]
+    pub delayed_recipients: Vec<SesRecipient>,
+}
+
+/// SesRenderingFailure
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SesRenderingFailure {
+    pub error_message: Option<String>,
+    pub template_name: Option<String>,
+}
+
+/// SesSubscription
+/// Subscription preference change of a contact list
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SesSubscription {
+    pub contact_list: Option<String>,
+    pub timestamp: Option<String>,
+    pub source: Option<String>,
+    pub new_topic_preferences: Option<Value>,
+    pub old_topic_preferences: Option<Value>,
+}
+
+/// SesOpen
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SesOpen {
+    pub timestamp: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+/// SesClick
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SesClick {
+    pub timestamp: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub link: Option<String>,
+    pub link_tags: Option<Value>,
+}
+
+/// SesEvent
+/// SES notification or event-publishing record delivered through SNS
+/// Notifications use `notificationType`, event publishing uses `eventType`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SesEvent {
+    #[serde(rename = "notificationType", alias = "eventType")]
+    pub event_type: SesEventType,
+    pub mail: Option<SesMail>,
+    pub bounce: Option<SesBounce>,
+    pub complaint: Option<SesComplaint>,
+    pub delivery: Option<SesDelivery>,
+    pub send: Option<Value>,
+    pub reject: Option<SesReject>,
+    #[serde(rename = "deliveryDelay")]
+    pub delivery_delay: Option<SesDeliveryDelay>,
+    pub failure: Option<SesRenderingFailure>,
+    pub subscription: Option<SesSubscription>,
+    pub open: Option<SesOpen>,
+    pub click: Option<SesClick>,
+}
+
+impl SesEvent {
+    /// message_id
+    /// SES message ID of the original message
+    pub fn message_id(&self) -> Option<&str> {
+        self.mail.as_ref().map(|mail| mail.message_id.as_str())
+    }
+
+    /// timestamp
+    /// Time the event occurred, falling back to the send time of the message
+    pub fn timestamp(&self) -> Option<&str> {
+        let timestamp = match self.event_type {
+            SesEventType::Bounce => self.bounce.as_ref().and_then(|e| e.timestamp.as_deref()),
+            SesEventType::Complaint => self.complaint.as_ref().and_then(|e| e.timestamp.as_deref()),
+            SesEventType::Delivery => self.delivery.as_ref().and_then(|e| e.timestamp.as_deref()),
+            SesEventType::DeliveryDelay => self
+                .delivery_delay
+                .as_ref()
+                .and_then(|e| e.timestamp.as_deref()),
+            SesEventType::Subscription => self
+                .subscription
+                .as_ref()
+                .and_then(|e| e.timestamp.as_deref()),
+            SesEventType::Open => self.open.as_ref().and_then(|e| e.timestamp.as_deref()),
+            SesEventType::Click => self.click.as_ref().and_then(|e| e.timestamp.as_deref()),
+            _ => None,
+        };
+        timestamp.or_else(|| {
+            self.mail
+                .as_ref()
+                .and_then(|mail| mail.timestamp.as_deref())
+        })
+    }
+
+    /// diagnostic_code
+    /// First diagnostic code reported for the bounced or delayed recipients
+    pub fn diagnostic_code(&self) -> Option<&str> {
+        let recipients = match self.event_type {
+            SesEventType::Bounce => self.bounce.as_ref().map(|e| &e.bounced_recipients),
+            SesEventType::DeliveryDelay => {
+                self.delivery_delay.as_ref().map(|e| &e.delayed_recipients)
+            }
+            _ => None,
+        }?;
+        recipients
+            .iter()
+            .find_map(|recipient| recipient.diagnostic_code.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bounce_notification() {
+        let event: SesEvent = serde_json::from_value(serde_json::json!({
+            "notificationType": "Bounce",
+            "mail": {"messageId": "ses-1", "timestamp": "2025-01-01T00:00:00.000Z"},
+            "bounce": {
+                "bounceType": "Permanent",
+                "bounceSubType": "NoEmail",
+                "bouncedRecipients": [{
+                    "emailAddress": "gone@example.com",
+                    "action": "failed",
+                    "status": "5.1.1",
+                    "diagnosticCode": "smtp; 550 5.1.1 user unknown"
+                }],
+                "timestamp": "2025-01-01T00:00:01.000Z",
+                "reportingMTA": "dsn; a1-2.smtp-out.amazonses.com"
+            }
+        }))
+        .unwrap();
+        assert_eq!(event.event_type, SesEventType::Bounce);
+        assert_eq!(event.message_id(), Some("ses-1"));
+        assert_eq!(event.timestamp(), Some("2025-01-01T00:00:01.000Z"));
+        assert_eq!(
+            event.diagnostic_code(),
+            Some("smtp; 550 5.1.1 user unknown")
+        );
+        let bounce = event.bounce.unwrap();
+        assert_eq!(bounce.bounce_sub_type.as_deref(), Some("NoEmail"));
+        assert_eq!(
+            bounce.bounced_recipients[0].email_address,
+            "gone@example.com"
+        );
+    }
+
+    #[test]
+    fn test_event_type_from_name() {
+        assert_eq!(
+            SesEventType::from_name("bounced"),
+            Some(SesEventType::Bounce)
+        );
+        assert_eq!(SesEventType::from_name("Open"), Some(SesEventType::Open));
+        assert_eq!(SesEventType::from_name("click"), Some(SesEventType::Click));
+        assert_eq!(SesEventType::from_name("bounces"), None);
+    }
+
+    #[test]
+    fn test_parse_published_events() {
+        let cases = [
+            (
+                serde_json::json!({"eventType": "Rendering Failure", "mail": {"messageId": "m"},
+                    "failure": {"errorMessage": "Attribute 'name' is not present", "templateName": "welcome"}}),
+                SesEventType::RenderingFailure,
+            ),
+            (
+                serde_json::json!({"eventType": "DeliveryDelay", "mail": {"messageId": "m"},
+                    "deliveryDelay": {"delayType": "MailboxFull", "delayedRecipients": []}}),
+                SesEventType::DeliveryDelay,
+            ),
+            (
+                serde_json::json!({"eventType": "Click", "mail": {"messageId": "m"},
+                    "click": {"link": "https://example.com", "ipAddress": "192.0.2.1"}}),
+                SesEventType::Click,
+            ),
+            (
+                serde_json::json!({"eventType": "Reject", "mail": {"messageId": "m"},
+                    "reject": {"reason": "Bad content"}}),
+                SesEventType::Reject,
+            ),
+            (
+                serde_json::json!({"eventType": "Send", "mail": {"messageId": "m"}, "send": {}}),
+                SesEventType::Send,
+            ),
+            (
+                serde_json::json!({"notificationType": "AmazonSnsSubscriptionSucceeded"}),
+                SesEventType::Other("AmazonSnsSubscriptionSucceeded".to_string()),
+            ),
+        ];
+        for (value, expected) in cases {
+            let event: SesEvent = serde_json::from_value(value).unwrap();
+            assert_eq!(event.event_type, expected);
+        }
+    }
+}
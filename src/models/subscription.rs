@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+/// SubscriptionStatus
+/// SNS subscription confirmation status
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SubscriptionStatus {
+    Pending,
+    Confirmed,
+}
+
+impl SubscriptionStatus {
+    /// as_str
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubscriptionStatus::Pending => "pending",
+            SubscriptionStatus::Confirmed => "confirmed",
+        }
+    }
+}
+
+/// SnsSubscription
+/// SNS topic subscription of the event endpoint
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SnsSubscription {
+    pub id: Option<i32>,
+    pub topic_arn: String,
+    pub status: String,
+    pub error: Option<String>,
+    pub created_at: Option<String>,
+    pub confirmed_at: Option<String>,
+}
+
+impl SnsSubscription {
+    /// save
+    /// Record the latest confirmation attempt for the topic
+    /// `confirmed_at` is set when the status becomes confirmed
+    pub async fn save(
+        db_pool: &SqlitePool,
+        topic_arn: &str,
+        status: SubscriptionStatus,
+        error: Option<String>,
+    ) -> Result<Self, sqlx::Error> {
+        let status = status.as_str();
+        let subscription = sqlx::query_as!(
+            SnsSubscription,
+            r#"
+            INSERT INTO sns_subscriptions (topic_arn, status, error, created_at, updated_at, confirmed_at)
+            VALUES (?, ?, ?, datetime('now'), datetime('now'),
+                    CASE WHEN ? = 'confirmed' THEN datetime('now') END)
+            ON CONFLICT (topic_arn) DO UPDATE
+            SET status = excluded.status,
+                error = excluded.error,
+                updated_at = excluded.updated_at,
+                confirmed_at = COALESCE(excluded.confirmed_at, sns_subscriptions.confirmed_at)
+            RETURNING id as "id?: i32",
+                      topic_arn,
+                      status,
+                      error,
+                      created_at as "created_at?: String",
+                      confirmed_at as "confirmed_at?: String"
+            "#,
+            topic_arn,
+            status,
+            error,
+            status,
+        )
+        .fetch_one(db_pool)
+        .await?;
+        Ok(subscription)
+    }
+
+    /// list
+    /// Retrieve every subscription, newest first
+    pub async fn list(db_pool: &SqlitePool) -> Result<Vec<Self>, sqlx::Error> {
+        let subscriptions = sqlx::query_as!(
+            SnsSubscription,
+            r#"
+            SELECT id as "id?: i32",
+                   topic_arn,
+                   status,
+                   error,
+                   created_at as "created_at?: String",
+                   confirmed_at as "confirmed_at?: String"
+            FROM sns_subscriptions
+            ORDER BY id DESC
+            "#,
+        )
+        .fetch_all(db_pool)
+        .await?;
+        Ok(subscriptions)
+    }
+}
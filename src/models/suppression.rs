@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteExecutor, SqlitePool};
+
+/// SUPPRESSION_SOURCE_SES
+/// Source of suppressions created from SES bounce/complaint notifications
+pub const SUPPRESSION_SOURCE_SES: &str = "ses";
+
+/// SUPPRESSION_SOURCE_API
+/// Source of suppressions added through the API
+pub const SUPPRESSION_SOURCE_API: &str = "api";
+
+/// Suppression
+/// Recipient address that must not be mailed again
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Suppression {
+    pub id: Option<i32>,
+    pub email: String,
+    pub reason: String,
+    pub source: String,
+    pub created_at: Option<String>,
+}
+
+/// normalize_email
+/// Addresses are compared case-insensitively and without surrounding whitespace
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+impl Suppression {
+    /// save
+    /// Add the address to the suppression list
+    /// If the address is already suppressed, its reason and source are replaced
+    pub async fn save<'e, E: SqliteExecutor<'e>>(self, executor: E) -> Result<Self, sqlx::Error> {
+        let email = normalize_email(&self.email);
+        let suppression = sqlx::query!(
+            r#"
+            INSERT INTO suppressions (email, reason, source, created_at)
+            VALUES (?, ?, ?, datetime('now'))
+            ON CONFLICT (email) DO UPDATE
+            SET reason = excluded.reason,
+                source = excluded.source
+            RETURNING id as "id: i32", created_at as "created_at: String"
+            "#,
+            email,
+            self.reason,
+            self.source,
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(Self {
+            id: Some(suppression.id),
+            email,
+            created_at: Some(suppression.created_at),
+            ..self
+        })
+    }
+
+    /// list
+    /// Retrieve every suppressed address, newest first
+    pub async fn list(db_pool: &SqlitePool) -> Result<Vec<Self>, sqlx::Error> {
+        let suppressions = sqlx::query_as!(
+            Suppression,
+            r#"
+            SELECT id as "id?: i32",
+                   email,
+                   reason,
+                   source,
+                   created_at as "created_at?: String"
+            FROM suppressions
+            ORDER BY id DESC
+            "#,
+        )
+        .fetch_all(db_pool)
+        .await?;
+        Ok(suppressions)
+    }
+
+    /// delete
+    /// Remove the address from the suppression list
+    /// Returns false if the address was not suppressed
+    pub async fn delete(db_pool: &SqlitePool, email: &str) -> Result<bool, sqlx::Error> {
+        let email = normalize_email(email);
+        let result = sqlx::query!("DELETE FROM suppressions WHERE email = ?", email)
+            .execute(db_pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// is_suppressed
+    /// Check whether the address is on the suppression list
+    pub async fn is_suppressed(db_pool: &SqlitePool, email: &str) -> Result<bool, sqlx::Error> {
+        let email = normalize_email(email);
+        let record = sqlx::query!(
+            r#"SELECT COUNT(*) as "count: i64" FROM suppressions WHERE email = ?"#,
+            email,
+        )
+        .fetch_one(db_pool)
+        .await?;
+        Ok(record.count > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suppression(email: &str, reason: &str) -> Suppression {
+        Suppression {
+            id: None,
+            email: email.to_string(),
+            reason: reason.to_string(),
+            source: SUPPRESSION_SOURCE_API.to_string(),
+            created_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_suppression_lifecycle() {
+        let db_pool = crate::tests::db_pool().await;
+        let saved = suppression(" Jane@Example.com ", "Manual")
+            .save(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(saved.email, "jane@example.com");
+        assert!(Suppression::is_suppressed(&db_pool, "JANE@example.com")
+            .await
+            .unwrap());
+        assert!(!Suppression::is_suppressed(&db_pool, "john@example.com")
+            .await
+            .unwrap());
+
+        // Saving the same address again replaces the reason instead of failing
+        suppression("jane@example.com", "Complaint")
+            .save(&db_pool)
+            .await
+            .unwrap();
+        let suppressions = Suppression::list(&db_pool).await.unwrap();
+        assert_eq!(suppressions.len(), 1);
+        assert_eq!(suppressions[0].reason, "Complaint");
+
+        assert!(Suppression::delete(&db_pool, "Jane@example.com")
+            .await
+            .unwrap());
+        assert!(!Suppression::delete(&db_pool, "jane@example.com")
+            .await
+            .unwrap());
+        assert!(!Suppression::is_suppressed(&db_pool, "jane@example.com")
+            .await
+            .unwrap());
+    }
+}
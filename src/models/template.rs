@@ -0,0 +1,275 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+/// EmailTemplate
+/// Stored email template at a specific version
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EmailTemplate {
+    pub id: Option<i32>,
+    pub name: String,
+    pub version: i32,
+    pub subject: String,
+    pub content: String,
+    pub created_at: Option<String>,
+}
+
+impl EmailTemplate {
+    /// save
+    /// Create a new template with its first version
+    pub async fn save(self, db_pool: &SqlitePool) -> Result<Self, sqlx::Error> {
+        let mut tx = db_pool.begin().await?;
+        let template = sqlx::query!(
+            r#"
+            INSERT INTO templates (name, created_at, updated_at)
+            VALUES (?, datetime('now'), datetime('now'))
+            RETURNING id
+            "#,
+            self.name,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let version = sqlx::query!(
+            r#"
+            INSERT INTO template_versions (template_id, version, subject, content, created_at)
+            VALUES (?, 1, ?, ?, datetime('now'))
+            RETURNING created_at as "created_at: String"
+            "#,
+            template.id,
+            self.subject,
+            self.content,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(Self {
+            id: Some(template.id as i32),
+            version: 1,
+            created_at: Some(version.created_at),
+            ..self
+        })
+    }
+
+    /// save_version
+    /// Store a new version of an existing template
+    /// Returns None if the template does not exist or was deleted
+    pub async fn save_version(
+        db_pool: &SqlitePool,
+        id: i32,
+        name: Option<String>,
+        subject: &str,
+        content: &str,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let mut tx = db_pool.begin().await?;
+        let Some(template) = sqlx::query!(
+            r#"
+            UPDATE templates
+            SET name = COALESCE(?, name),
+                updated_at = datetime('now')
+            WHERE id = ? AND deleted_at IS NULL
+            RETURNING name
+            "#,
+            name,
+            id,
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        else {
+            return Ok(None);
+        };
+        let version = sqlx::query!(
+            r#"
+            INSERT INTO template_versions (template_id, version, subject, content, created_at)
+            SELECT ?, COALESCE(MAX(version), 0) + 1, ?, ?, datetime('now')
+            FROM template_versions
+            WHERE template_id = ?
+            RETURNING version as "version: i32", created_at as "created_at: String"
+            "#,
+            id,
+            subject,
+            content,
+            id,
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(Some(Self {
+            id: Some(id),
+            name: template.name,
+            version: version.version,
+            subject: subject.to_string(),
+            content: content.to_string(),
+            created_at: Some(version.created_at),
+        }))
+    }
+
+    /// find
+    /// Retrieve a template at the given version, or its latest version
+    pub async fn find(
+        db_pool: &SqlitePool,
+        id: i32,
+        version: Option<i32>,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let template = sqlx::query_as!(
+            EmailTemplate,
+            r#"
+            SELECT t.id as "id?: i32",
+                   t.name,
+                   v.version as "version: i32",
+                   v.subject,
+                   v.content,
+                   v.created_at as "created_at?: String"
+            FROM templates t
+            JOIN template_versions v ON v.template_id = t.id
+            WHERE t.id = ? AND t.deleted_at IS NULL
+            AND (? IS NULL OR v.version = ?)
+            ORDER BY v.version DESC
+            LIMIT 1
+            "#,
+            id,
+            version,
+            version,
+        )
+        .fetch_optional(db_pool)
+        .await?;
+        Ok(template)
+    }
+
+    /// list
+    /// Retrieve the latest version of every template
+    pub async fn list(db_pool: &SqlitePool) -> Result<Vec<Self>, sqlx::Error> {
+        let templates = sqlx::query_as!(
+            EmailTemplate,
+            r#"
+            SELECT t.id as "id?: i32",
+                   t.name,
+                   v.version as "version: i32",
+                   v.subject,
+                   v.content,
+                   v.created_at as "created_at?: String"
+            FROM templates t
+            JOIN template_versions v ON v.template_id = t.id
+            WHERE t.deleted_at IS NULL
+            AND v.version = (
+                SELECT MAX(version) FROM template_versions WHERE template_id = t.id
+            )
+            ORDER BY t.id
+            "#,
+        )
+        .fetch_all(db_pool)
+        .await?;
+        Ok(templates)
+    }
+
+    /// list_versions
+    /// Retrieve every version of a template, newest first
+    pub async fn list_versions(db_pool: &SqlitePool, id: i32) -> Result<Vec<Self>, sqlx::Error> {
+        let templates = sqlx::query_as!(
+            EmailTemplate,
+            r#"
+            SELECT t.id as "id?: i32",
+                   t.name,
+                   v.version as "version: i32",
+                   v.subject,
+                   v.content,
+                   v.created_at as "created_at?: String"
+            FROM templates t
+            JOIN template_versions v ON v.template_id = t.id
+            WHERE t.id = ? AND t.deleted_at IS NULL
+            ORDER BY v.version DESC
+            "#,
+            id,
+        )
+        .fetch_all(db_pool)
+        .await?;
+        Ok(templates)
+    }
+
+    /// delete
+    /// Soft delete a template; versions are kept for auditing sent emails
+    /// Returns false if the template does not exist
+    pub async fn delete(db_pool: &SqlitePool, id: i32) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE templates
+            SET deleted_at = datetime('now'),
+                updated_at = datetime('now')
+            WHERE id = ? AND deleted_at IS NULL
+            "#,
+            id,
+        )
+        .execute(db_pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(name: &str) -> EmailTemplate {
+        EmailTemplate {
+            id: None,
+            name: name.to_string(),
+            version: 0,
+            subject: "subject v1".to_string(),
+            content: "content v1".to_string(),
+            created_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_template_versions() {
+        let db_pool = crate::tests::db_pool().await;
+        let saved = template("welcome").save(&db_pool).await.unwrap();
+        let id = saved.id.unwrap();
+        assert_eq!(saved.version, 1);
+
+        let updated = EmailTemplate::save_version(&db_pool, id, None, "subject v2", "content v2")
+            .await
+            .unwrap()
+            .expect("Template should exist");
+        assert_eq!(updated.version, 2);
+        assert_eq!(updated.name, "welcome");
+
+        let latest = EmailTemplate::find(&db_pool, id, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.version, 2);
+        assert_eq!(latest.subject, "subject v2");
+
+        let first = EmailTemplate::find(&db_pool, id, Some(1))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.subject, "subject v1");
+
+        let versions = EmailTemplate::list_versions(&db_pool, id).await.unwrap();
+        assert_eq!(versions.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_deleted_template_is_hidden() {
+        let db_pool = crate::tests::db_pool().await;
+        let id = template("old").save(&db_pool).await.unwrap().id.unwrap();
+        template("kept").save(&db_pool).await.unwrap();
+
+        assert!(EmailTemplate::delete(&db_pool, id).await.unwrap());
+        assert!(!EmailTemplate::delete(&db_pool, id).await.unwrap());
+        assert!(EmailTemplate::find(&db_pool, id, None)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(EmailTemplate::save_version(&db_pool, id, None, "s", "c")
+            .await
+            .unwrap()
+            .is_none());
+
+        let templates = EmailTemplate::list(&db_pool).await.unwrap();
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].name, "kept");
+    }
+}
@@ -0,0 +1,412 @@
+use crate::models::request::{EmailMessageStatus, EmailRequest};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqliteExecutor, SqlitePool};
+
+/// Topic
+/// Metadata of a topic; requests refer to it by `topic_id`
+/// Topics are registered implicitly by the first message that uses them, or explicitly through the API
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Topic {
+    #[serde(skip_deserializing)]
+    pub id: Option<i64>,
+    pub topic_id: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(skip_deserializing)]
+    pub created_by: Option<String>,
+    #[serde(skip_deserializing)]
+    pub first_sent_at: Option<String>,
+    #[serde(skip_deserializing)]
+    pub last_sent_at: Option<String>,
+    /// Set while the topic is paused; its pending requests are held until resumed
+    #[serde(skip_deserializing)]
+    pub paused_at: Option<String>,
+    #[serde(skip_deserializing)]
+    pub created_at: Option<String>,
+    #[serde(skip_deserializing)]
+    pub updated_at: Option<String>,
+}
+
+/// TopicCounts
+/// Request counts of a topic by status; `pending` requests are waiting to be sent
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TopicCounts {
+    pub total: i64,
+    pub pending: i64,
+    pub sent: i64,
+    pub failed: i64,
+    pub stopped: i64,
+    pub suppressed: i64,
+    pub paused: i64,
+}
+
+/// TopicSummary
+/// Topic with its request counts
+#[derive(Serialize, Debug)]
+pub struct TopicSummary {
+    #[serde(flatten)]
+    pub topic: Topic,
+    pub counts: TopicCounts,
+}
+
+/// TopicRow
+struct TopicRow {
+    id: i64,
+    topic_id: String,
+    name: Option<String>,
+    description: Option<String>,
+    tags: Option<String>,
+    created_by: Option<String>,
+    first_sent_at: Option<String>,
+    last_sent_at: Option<String>,
+    paused_at: Option<String>,
+    created_at: String,
+    updated_at: String,
+}
+
+impl From<TopicRow> for Topic {
+    fn from(row: TopicRow) -> Self {
+        Topic {
+            id: Some(row.id),
+            topic_id: row.topic_id,
+            name: row.name,
+            description: row.description,
+            tags: split_tags(row.tags),
+            created_by: row.created_by,
+            first_sent_at: row.first_sent_at,
+            last_sent_at: row.last_sent_at,
+            paused_at: row.paused_at,
+            created_at: Some(row.created_at),
+            updated_at: Some(row.updated_at),
+        }
+    }
+}
+
+/// join_tags
+/// Tags are stored comma-separated
+fn join_tags(tags: &[String]) -> Option<String> {
+    if tags.is_empty() {
+        None
+    } else {
+        Some(tags.join(","))
+    }
+}
+
+/// split_tags
+fn split_tags(tags: Option<String>) -> Vec<String> {
+    tags.map(|v| v.split(',').map(String::from).collect())
+        .unwrap_or_default()
+}
+
+impl Topic {
+    /// ensure
+    /// Register the topic if it does not exist yet; existing metadata is left untouched
+    pub async fn ensure<'e, E: SqliteExecutor<'e>>(
+        executor: E,
+        topic_id: &str,
+        created_by: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            INSERT INTO topics (topic_id, created_by, created_at, updated_at)
+            VALUES (?, ?, datetime('now'), datetime('now'))
+            ON CONFLICT (topic_id) DO NOTHING
+            "#,
+            topic_id,
+            created_by,
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
+    /// save
+    /// Register the topic, or update the name, description and tags of an existing one
+    /// The creator of an existing topic is kept
+    pub async fn save(self, db_pool: &SqlitePool) -> Result<Self, sqlx::Error> {
+        let tags = join_tags(&self.tags);
+        let row = sqlx::query_as!(
+            TopicRow,
+            r#"
+            INSERT INTO topics (topic_id, name, description, tags, created_by, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, datetime('now'), datetime('now'))
+            ON CONFLICT (topic_id) DO UPDATE
+            SET name = excluded.name,
+                description = excluded.description,
+                tags = excluded.tags,
+                updated_at = datetime('now')
+            RETURNING id as "id!: i64",
+                      topic_id,
+                      name,
+                      description,
+                      tags,
+                      created_by,
+                      first_sent_at as "first_sent_at?: String",
+                      last_sent_at as "last_sent_at?: String",
+                      paused_at as "paused_at?: String",
+                      created_at as "created_at!: String",
+                      updated_at as "updated_at!: String"
+            "#,
+            self.topic_id,
+            self.name,
+            self.description,
+            tags,
+            self.created_by,
+        )
+        .fetch_one(db_pool)
+        .await?;
+        Ok(row.into())
+    }
+
+    /// find
+    /// Retrieve a topic by its topic ID
+    pub async fn find(db_pool: &SqlitePool, topic_id: &str) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query_as!(
+            TopicRow,
+            r#"
+            SELECT id as "id!: i64",
+                   topic_id,
+                   name,
+                   description,
+                   tags,
+                   created_by,
+                   first_sent_at as "first_sent_at?: String",
+                   last_sent_at as "last_sent_at?: String",
+                   paused_at as "paused_at?: String",
+                   created_at as "created_at!: String",
+                   updated_at as "updated_at!: String"
+            FROM topics
+            WHERE topic_id = ?
+            "#,
+            topic_id,
+        )
+        .fetch_optional(db_pool)
+        .await?;
+        Ok(row.map(Topic::from))
+    }
+
+    /// mark_sent
+    /// Record a send of the topic in its first/last sent times
+    pub async fn mark_sent(db_pool: &SqlitePool, topic_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE topics
+            SET first_sent_at = COALESCE(first_sent_at, datetime('now')),
+                last_sent_at = datetime('now')
+            WHERE topic_id = ?
+            "#,
+            topic_id,
+        )
+        .execute(db_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// is_paused
+    pub async fn is_paused(db_pool: &SqlitePool, topic_id: &str) -> Result<bool, sqlx::Error> {
+        let paused = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!: i64"
+            FROM topics
+            WHERE topic_id = ? AND paused_at IS NOT NULL
+            "#,
+            topic_id,
+        )
+        .fetch_one(db_pool)
+        .await?;
+        Ok(paused > 0)
+    }
+
+    /// pause
+    /// Pause the topic and hold its pending requests, registering the topic if needed
+    /// Returns the number of held requests, or None if the topic was already paused
+    pub async fn pause(
+        db_pool: &SqlitePool,
+        topic_id: &str,
+        created_by: Option<&str>,
+    ) -> Result<Option<u64>, sqlx::Error> {
+        let mut tx = db_pool.begin().await?;
+        Topic::ensure(&mut *tx, topic_id, created_by).await?;
+        let result = sqlx::query!(
+            r#"
+            UPDATE topics
+            SET paused_at = datetime('now'),
+                updated_at = datetime('now')
+            WHERE topic_id = ? AND paused_at IS NULL
+            "#,
+            topic_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        let held = EmailRequest::hold_topic(&mut *tx, topic_id).await?;
+        tx.commit().await?;
+        Ok(Some(held))
+    }
+
+    /// pause_for_breach
+    /// Pause the topic whose bounce/complaint rate is over the limit, registering the topic if needed
+    /// The breach is recorded on the topic, so it is acted on once even if the topic is resumed while still over the limit
+    /// Returns the number of held requests, or None if the breach was already recorded
+    pub async fn pause_for_breach(
+        db_pool: &SqlitePool,
+        topic_id: &str,
+    ) -> Result<Option<u64>, sqlx::Error> {
+        let mut tx = db_pool.begin().await?;
+        Topic::ensure(&mut *tx, topic_id, None).await?;
+        let result = sqlx::query!(
+            r#"
+            UPDATE topics
+            SET rate_breached_at = datetime('now'),
+                paused_at = COALESCE(paused_at, datetime('now')),
+                updated_at = datetime('now')
+            WHERE topic_id = ? AND rate_breached_at IS NULL
+            "#,
+            topic_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        let held = EmailRequest::hold_topic(&mut *tx, topic_id).await?;
+        tx.commit().await?;
+        Ok(Some(held))
+    }
+
+    /// list_breached
+    /// Retrieve the IDs of topics with a recorded rate breach
+    pub async fn list_breached(db_pool: &SqlitePool) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar!(
+            r#"
+            SELECT topic_id
+            FROM topics
+            WHERE rate_breached_at IS NOT NULL
+            "#,
+        )
+        .fetch_all(db_pool)
+        .await
+    }
+
+    /// clear_breach
+    /// Forget the recorded rate breach once the topic is back under the limit
+    /// The topic stays paused until resumed
+    pub async fn clear_breach(db_pool: &SqlitePool, topic_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE topics
+            SET rate_breached_at = NULL
+            WHERE topic_id = ?
+            "#,
+            topic_id,
+        )
+        .execute(db_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// resume
+    /// Resume the topic and release its held requests
+    /// Returns the number of released requests, or None if the topic was not paused
+    pub async fn resume(db_pool: &SqlitePool, topic_id: &str) -> Result<Option<u64>, sqlx::Error> {
+        let mut tx = db_pool.begin().await?;
+        let result = sqlx::query!(
+            r#"
+            UPDATE topics
+            SET paused_at = NULL,
+                updated_at = datetime('now')
+            WHERE topic_id = ? AND paused_at IS NOT NULL
+            "#,
+            topic_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        let released = EmailRequest::release_topic(&mut *tx, topic_id).await?;
+        tx.commit().await?;
+        Ok(Some(released))
+    }
+
+    /// list
+    /// Retrieve topics with their request counts, newest first
+    /// Pages continue from `before_id`, the ID of the last topic of the previous page
+    pub async fn list(
+        db_pool: &SqlitePool,
+        before_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<TopicSummary>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT t.id as "id!: i64",
+                   t.topic_id,
+                   t.name,
+                   t.description,
+                   t.tags,
+                   t.created_by,
+                   t.first_sent_at as "first_sent_at?: String",
+                   t.last_sent_at as "last_sent_at?: String",
+                   t.paused_at as "paused_at?: String",
+                   t.created_at as "created_at!: String",
+                   t.updated_at as "updated_at!: String",
+                   COUNT(r.id) as "total!: i64",
+                   COALESCE(SUM(r.status IN (?, ?)), 0) as "pending!: i64",
+                   COALESCE(SUM(r.status = ?), 0) as "sent!: i64",
+                   COALESCE(SUM(r.status = ?), 0) as "failed!: i64",
+                   COALESCE(SUM(r.status = ?), 0) as "stopped!: i64",
+                   COALESCE(SUM(r.status = ?), 0) as "suppressed!: i64",
+                   COALESCE(SUM(r.status = ?), 0) as "paused!: i64"
+            FROM topics t
+            LEFT JOIN email_requests r ON r.topic_id = t.topic_id
+            WHERE (? IS NULL OR t.id < ?)
+            GROUP BY t.id
+            ORDER BY t.id DESC
+            LIMIT ?
+            "#,
+            EmailMessageStatus::Created as i32,
+            EmailMessageStatus::Processed as i32,
+            EmailMessageStatus::Sent as i32,
+            EmailMessageStatus::Failed as i32,
+            EmailMessageStatus::Stopped as i32,
+            EmailMessageStatus::Suppressed as i32,
+            EmailMessageStatus::Paused as i32,
+            before_id,
+            before_id,
+            limit,
+        )
+        .fetch_all(db_pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| TopicSummary {
+                topic: Topic::from(TopicRow {
+                    id: row.id,
+                    topic_id: row.topic_id,
+                    name: row.name,
+                    description: row.description,
+                    tags: row.tags,
+                    created_by: row.created_by,
+                    first_sent_at: row.first_sent_at,
+                    last_sent_at: row.last_sent_at,
+                    paused_at: row.paused_at,
+                    created_at: row.created_at,
+                    updated_at: row.updated_at,
+                }),
+                counts: TopicCounts {
+                    total: row.total,
+                    pending: row.pending,
+                    sent: row.sent,
+                    failed: row.failed,
+                    stopped: row.stopped,
+                    suppressed: row.suppressed,
+                    paused: row.paused,
+                },
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,409 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+/// DELIVERY_STATUS_PENDING
+/// Delivery waiting for its next attempt
+pub const DELIVERY_STATUS_PENDING: &str = "pending";
+
+/// DELIVERY_STATUS_DELIVERED
+/// Delivery acknowledged with a 2xx response
+pub const DELIVERY_STATUS_DELIVERED: &str = "delivered";
+
+/// DELIVERY_STATUS_FAILED
+/// Delivery that ran out of attempts (dead letter)
+pub const DELIVERY_STATUS_FAILED: &str = "failed";
+
+/// Webhook
+/// Endpoint that receives delivery events, optionally filtered by topic and event type
+/// The secret is only returned when the webhook is created
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Webhook {
+    pub id: Option<i32>,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub topic_id: Option<String>,
+    pub event_types: Vec<String>,
+    #[serde(skip)]
+    pub last_result_id: i64,
+    pub created_at: Option<String>,
+}
+
+/// join_event_types
+/// Event types are stored comma-separated; NULL matches every event
+fn join_event_types(event_types: &[String]) -> Option<String> {
+    if event_types.is_empty() {
+        None
+    } else {
+        Some(event_types.join(","))
+    }
+}
+
+/// split_event_types
+fn split_event_types(event_types: Option<String>) -> Vec<String> {
+    event_types
+        .map(|v| v.split(',').map(String::from).collect())
+        .unwrap_or_default()
+}
+
+impl Webhook {
+    /// matches
+    /// Whether the webhook subscribes to the event type
+    pub fn matches(&self, event_type: &str) -> bool {
+        self.event_types.is_empty() || self.event_types.iter().any(|t| t == event_type)
+    }
+
+    /// save
+    /// Register the webhook
+    /// Only results saved after registration are delivered
+    pub async fn save(self, db_pool: &SqlitePool) -> Result<Self, sqlx::Error> {
+        let event_types = join_event_types(&self.event_types);
+        let instance = sqlx::query!(
+            r#"
+            INSERT INTO webhooks (url, secret, topic_id, event_types, last_result_id, created_at)
+            VALUES (?, ?, ?, ?, (SELECT COALESCE(MAX(id), 0) FROM email_results), datetime('now'))
+            RETURNING id as "id: i32", last_result_id, created_at as "created_at: String"
+            "#,
+            self.url,
+            self.secret,
+            self.topic_id,
+            event_types,
+        )
+        .fetch_one(db_pool)
+        .await?;
+
+        Ok(Self {
+            id: Some(instance.id),
+            last_result_id: instance.last_result_id,
+            created_at: Some(instance.created_at),
+            ..self
+        })
+    }
+
+    /// list
+    /// Retrieve every active webhook
+    pub async fn list(db_pool: &SqlitePool) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id as "id: i32",
+                   url,
+                   secret,
+                   topic_id,
+                   event_types,
+                   last_result_id,
+                   created_at as "created_at: String"
+            FROM webhooks
+            WHERE deleted_at IS NULL
+            ORDER BY id
+            "#,
+        )
+        .fetch_all(db_pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| Webhook {
+                id: Some(row.id),
+                url: row.url,
+                secret: row.secret,
+                topic_id: row.topic_id,
+                event_types: split_event_types(row.event_types),
+                last_result_id: row.last_result_id,
+                created_at: Some(row.created_at),
+            })
+            .collect())
+    }
+
+    /// delete
+    /// Soft delete the webhook; its pending deliveries are no longer attempted
+    /// Returns false if no active webhook has the ID
+    pub async fn delete(db_pool: &SqlitePool, id: i32) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE webhooks
+            SET deleted_at = datetime('now')
+            WHERE id = ? AND deleted_at IS NULL
+            "#,
+            id,
+        )
+        .execute(db_pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// WebhookEvent
+/// Payload delivered for a saved result
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebhookEvent {
+    pub result_id: i64,
+    pub request_id: i64,
+    pub topic_id: String,
+    pub email: String,
+    pub message_id: Option<String>,
+    pub event_type: String,
+    pub bounce_type: Option<String>,
+    pub bounce_sub_type: Option<String>,
+    pub complaint_feedback_type: Option<String>,
+    pub diagnostic_code: Option<String>,
+    pub reject_reason: Option<String>,
+    pub delay_type: Option<String>,
+    pub link: Option<String>,
+    pub event_at: Option<String>,
+    pub created_at: String,
+}
+
+impl WebhookEvent {
+    /// list_after
+    /// Retrieve results saved after `result_id`, optionally limited to a topic
+    pub async fn list_after(
+        db_pool: &SqlitePool,
+        result_id: i64,
+        topic_id: Option<&str>,
+        limit: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let events = sqlx::query_as!(
+            WebhookEvent,
+            r#"
+            SELECT res.id as "result_id!: i64",
+                   res.request_id as "request_id!: i64",
+                   req.topic_id,
+                   req.email,
+                   req.message_id,
+                   res.status as event_type,
+                   res.bounce_type,
+                   res.bounce_sub_type,
+                   res.complaint_feedback_type,
+                   res.diagnostic_code,
+                   res.reject_reason,
+                   res.delay_type,
+                   res.link,
+                   res.event_at,
+                   res.created_at as "created_at!: String"
+            FROM email_results res
+            JOIN email_requests req ON req.id = res.request_id
+            WHERE res.id > ? AND (? IS NULL OR req.topic_id = ?)
+            ORDER BY res.id
+            LIMIT ?
+            "#,
+            result_id,
+            topic_id,
+            topic_id,
+            limit,
+        )
+        .fetch_all(db_pool)
+        .await?;
+        Ok(events)
+    }
+}
+
+/// WebhookDelivery
+/// Delivery of one result to one webhook
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub result_id: i64,
+    pub payload: String,
+    pub status: String,
+    pub attempts: i64,
+    pub next_attempt_at: String,
+    pub last_status_code: Option<i64>,
+    pub last_error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub delivered_at: Option<String>,
+}
+
+/// DueDelivery
+/// Pending delivery whose next attempt is due, with its endpoint
+pub struct DueDelivery {
+    pub id: i64,
+    pub url: String,
+    pub secret: String,
+    pub payload: String,
+    pub attempts: i64,
+}
+
+impl WebhookDelivery {
+    /// enqueue
+    /// Create pending deliveries and move the webhook cursor to `last_result_id`
+    pub async fn enqueue(
+        db_pool: &SqlitePool,
+        webhook_id: i32,
+        deliveries: Vec<(i64, String)>,
+        last_result_id: i64,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = db_pool.begin().await?;
+        for (result_id, payload) in deliveries {
+            sqlx::query!(
+                r#"
+                INSERT INTO webhook_deliveries (webhook_id, result_id, payload, status, created_at, updated_at)
+                VALUES (?, ?, ?, 'pending', datetime('now'), datetime('now'))
+                ON CONFLICT (webhook_id, result_id) DO NOTHING
+                "#,
+                webhook_id,
+                result_id,
+                payload,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query!(
+            "UPDATE webhooks SET last_result_id = ? WHERE id = ?",
+            last_result_id,
+            webhook_id,
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// get_due
+    /// Retrieve pending deliveries of active webhooks whose next attempt is due
+    pub async fn get_due(
+        db_pool: &SqlitePool,
+        limit: i64,
+    ) -> Result<Vec<DueDelivery>, sqlx::Error> {
+        let deliveries = sqlx::query_as!(
+            DueDelivery,
+            r#"
+            SELECT d.id as "id!: i64",
+                   w.url,
+                   w.secret,
+                   d.payload,
+                   d.attempts
+            FROM webhook_deliveries d
+            JOIN webhooks w ON w.id = d.webhook_id
+            WHERE d.status = 'pending'
+            AND d.next_attempt_at <= datetime('now')
+            AND w.deleted_at IS NULL
+            ORDER BY d.next_attempt_at, d.id
+            LIMIT ?
+            "#,
+            limit,
+        )
+        .fetch_all(db_pool)
+        .await?;
+        Ok(deliveries)
+    }
+
+    /// mark_delivered
+    pub async fn mark_delivered(
+        db_pool: &SqlitePool,
+        id: i64,
+        status_code: i64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'delivered',
+                attempts = attempts + 1,
+                last_status_code = ?,
+                last_error = NULL,
+                updated_at = datetime('now'),
+                delivered_at = datetime('now')
+            WHERE id = ?
+            "#,
+            status_code,
+            id,
+        )
+        .execute(db_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// mark_attempt_failed
+    /// Schedule the next attempt in `retry_in_seconds`, or move the delivery to the dead letters when None
+    pub async fn mark_attempt_failed(
+        db_pool: &SqlitePool,
+        id: i64,
+        status_code: Option<i64>,
+        error: &str,
+        retry_in_seconds: Option<i64>,
+    ) -> Result<(), sqlx::Error> {
+        let status = match retry_in_seconds {
+            Some(_) => DELIVERY_STATUS_PENDING,
+            None => DELIVERY_STATUS_FAILED,
+        };
+        let offset = format!("+{} seconds", retry_in_seconds.unwrap_or(0));
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = ?,
+                attempts = attempts + 1,
+                next_attempt_at = datetime('now', ?),
+                last_status_code = ?,
+                last_error = ?,
+                updated_at = datetime('now')
+            WHERE id = ?
+            "#,
+            status,
+            offset,
+            status_code,
+            error,
+            id,
+        )
+        .execute(db_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// list
+    /// Retrieve deliveries with the status, newest first
+    pub async fn list(
+        db_pool: &SqlitePool,
+        status: &str,
+        webhook_id: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let deliveries = sqlx::query_as!(
+            WebhookDelivery,
+            r#"
+            SELECT id as "id!: i64",
+                   webhook_id,
+                   result_id,
+                   payload,
+                   status,
+                   attempts,
+                   next_attempt_at as "next_attempt_at!: String",
+                   last_status_code,
+                   last_error,
+                   created_at as "created_at!: String",
+                   updated_at as "updated_at!: String",
+                   delivered_at as "delivered_at?: String"
+            FROM webhook_deliveries
+            WHERE status = ? AND (? IS NULL OR webhook_id = ?)
+            ORDER BY id DESC
+            LIMIT ?
+            "#,
+            status,
+            webhook_id,
+            webhook_id,
+            limit,
+        )
+        .fetch_all(db_pool)
+        .await?;
+        Ok(deliveries)
+    }
+
+    /// retry
+    /// Move a dead-lettered delivery back to pending with a fresh set of attempts
+    /// Returns false if no failed delivery has the ID
+    pub async fn retry(db_pool: &SqlitePool, id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'pending',
+                attempts = 0,
+                next_attempt_at = datetime('now'),
+                updated_at = datetime('now')
+            WHERE id = ? AND status = 'failed'
+            "#,
+            id,
+        )
+        .execute(db_pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,250 @@
+use crate::models::orphan_event::OrphanEvent;
+use crate::models::processed_event::ProcessedEvent;
+use crate::models::request::EmailRequest;
+use crate::models::result::EmailResult;
+use crate::models::ses_event::{SesEvent, SesEventType};
+use crate::models::suppression::{Suppression, SUPPRESSION_SOURCE_SES};
+use sqlx::SqlitePool;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+
+/// EventOutcome
+/// Result of processing an SES notification
+#[derive(Debug, PartialEq, Eq)]
+pub enum EventOutcome {
+    Saved,
+    /// The message is not an SES notification; nothing is stored
+    Ignored,
+    /// The SNS message was already processed
+    Duplicate,
+    /// No request matches the SES message ID yet; stored for reconciliation
+    Orphaned,
+}
+
+/// EventError
+/// Failure while processing an SES notification
+/// Details are logged where the failure happens; the notification should be delivered again
+#[derive(Debug)]
+pub enum EventError {
+    MissingMessageId,
+    Database,
+}
+
+/// suppressions_from_notification
+/// Builds suppressions for the recipients of a permanent bounce or a complaint
+fn suppressions_from_notification(event: &SesEvent) -> Vec<Suppression> {
+    let (recipients, reason) = match (&event.event_type, &event.bounce, &event.complaint) {
+        (SesEventType::Bounce, Some(bounce), _) if bounce.bounce_type == "Permanent" => {
+            let reason = match &bounce.bounce_sub_type {
+                Some(sub_type) => format!("Permanent bounce ({})", sub_type),
+                None => "Permanent bounce".to_string(),
+            };
+            (&bounce.bounced_recipients, reason)
+        }
+        (SesEventType::Complaint, _, Some(complaint)) => {
+            let reason = match &complaint.complaint_feedback_type {
+                Some(feedback_type) => format!("Complaint ({})", feedback_type),
+                None => "Complaint".to_string(),
+            };
+            (&complaint.complained_recipients, reason)
+        }
+        _ => return Vec::new(),
+    };
+    recipients
+        .iter()
+        .map(|recipient| Suppression {
+            id: None,
+            email: recipient.email_address.clone(),
+            reason: reason.clone(),
+            source: SUPPRESSION_SOURCE_SES.to_string(),
+            created_at: None,
+        })
+        .collect()
+}
+
+/// process_notification
+/// Saves the result carried by an SES notification (the `Message` of an SNS envelope)
+/// Shared by the SNS endpoint and the SQS poller
+/// The SNS MessageId is recorded in the same transaction as the result and the suppressions,
+/// so redeliveries are skipped only once everything is saved
+pub async fn process_notification(
+    db_pool: &SqlitePool,
+    sns_message_id: &str,
+    message: String,
+) -> Result<EventOutcome, EventError> {
+    let ses_event = match serde_json::from_str::<SesEvent>(&message) {
+        Ok(ses_event) => ses_event,
+        Err(e) => {
+            error!(
+                "Failed to parse SES notification: {:?}, message: {}",
+                e, message
+            );
+            return Ok(EventOutcome::Ignored);
+        }
+    };
+
+    let Some(ses_message_id) = ses_event.message_id().map(String::from) else {
+        error!(
+            "SES message_id not found in notification. SNS MessageId: {}.  Message: {:?}",
+            sns_message_id, ses_event
+        );
+        return Err(EventError::MissingMessageId);
+    };
+
+    // Events can arrive before the message ID is written, or belong to mail sent by other systems
+    let request_id =
+        match EmailRequest::get_request_id_by_message_id(db_pool, &ses_message_id).await {
+            Ok(request_id) => Some(request_id),
+            Err(sqlx::Error::RowNotFound) => None,
+            Err(e) => {
+                // Log *both* SNS and SES message IDs for debugging
+                error!(
+                "Failed to retrieve request_id. SNS MessageId: {}, SES MessageId: {}, Error: {:?}",
+                sns_message_id, ses_message_id, e
+            );
+                return Err(EventError::Database);
+            }
+        };
+
+    let outcome = match store_once(
+        db_pool,
+        sns_message_id,
+        &ses_message_id,
+        request_id,
+        &ses_event,
+        message,
+    )
+    .await
+    {
+        Ok(EventOutcome::Duplicate) => {
+            info!("Skipping duplicate SNS message: {}", sns_message_id);
+            return Ok(EventOutcome::Duplicate);
+        }
+        Ok(outcome) => outcome,
+        Err(e) => {
+            error!("Failed to save event to database: {:?}", e);
+            return Err(EventError::Database);
+        }
+    };
+    Ok(outcome)
+}
+
+/// store_once
+/// Saves the result, or the orphan event when no request matches, and the suppressions it implies,
+/// unless the SNS message was already processed
+async fn store_once(
+    db_pool: &SqlitePool,
+    sns_message_id: &str,
+    ses_message_id: &str,
+    request_id: Option<i32>,
+    ses_event: &SesEvent,
+    message: String,
+) -> Result<EventOutcome, sqlx::Error> {
+    let mut tx = db_pool.begin().await?;
+    if !ProcessedEvent::record(&mut *tx, sns_message_id).await? {
+        return Ok(EventOutcome::Duplicate);
+    }
+    let outcome = match request_id {
+        Some(request_id) => {
+            EmailResult::from_ses_event(request_id, ses_event, message)
+                .save(&mut *tx)
+                .await?;
+            EventOutcome::Saved
+        }
+        None => {
+            OrphanEvent::from_ses_event(sns_message_id, ses_message_id, ses_event, message)
+                .save(&mut *tx)
+                .await?;
+            EventOutcome::Orphaned
+        }
+    };
+    // Permanent bounces and complaints are never mailed again
+    for suppression in suppressions_from_notification(ses_event) {
+        suppression.save(&mut *tx).await?;
+    }
+    tx.commit().await?;
+    Ok(outcome)
+}
+
+/// reconcile_orphan_events
+/// Attaches orphan events to requests whose message ID has since been written
+/// Returns the number of reconciled events
+pub async fn reconcile_orphan_events(db_pool: &SqlitePool) -> Result<usize, sqlx::Error> {
+    let matched = OrphanEvent::get_matched(db_pool, 1000).await?;
+    let mut reconciled = 0;
+    for orphan in matched {
+        let ses_event = match serde_json::from_str::<SesEvent>(&orphan.raw) {
+            Ok(ses_event) => ses_event,
+            Err(e) => {
+                error!("Failed to parse orphan event {}: {:?}", orphan.id, e);
+                continue;
+            }
+        };
+        let mut tx = db_pool.begin().await?;
+        EmailResult::from_ses_event(orphan.request_id, &ses_event, orphan.raw)
+            .save(&mut *tx)
+            .await?;
+        OrphanEvent::delete(&mut *tx, orphan.id).await?;
+        tx.commit().await?;
+        reconciled += 1;
+    }
+    Ok(reconciled)
+}
+
+/// reconcile_orphans
+/// Periodically reconciles orphan events until shutdown
+pub async fn reconcile_orphans(db_pool: SqlitePool, shutdown: CancellationToken) {
+    while !shutdown.is_cancelled() {
+        match reconcile_orphan_events(&db_pool).await {
+            Ok(0) => {}
+            Ok(reconciled) => println!("Reconciled {} orphan events", reconciled),
+            Err(e) => eprintln!("Failed to reconcile orphan events: {:?}", e),
+        }
+        tokio::select! {
+            _ = shutdown.cancelled() => {}
+            _ = tokio::time::sleep(Duration::from_secs(60)) => {}
+        }
+    }
+}
+
+/// prune_processed_events
+/// Periodically deletes dedup keys older than the retention period until shutdown
+/// SNS stops retrying long before the keys expire
+pub async fn prune_processed_events(
+    db_pool: SqlitePool,
+    retention_days: i64,
+    shutdown: CancellationToken,
+) {
+    while !shutdown.is_cancelled() {
+        match ProcessedEvent::prune(&db_pool, retention_days).await {
+            Ok(0) => {}
+            Ok(pruned) => println!("Pruned {} processed event keys", pruned),
+            Err(e) => eprintln!("Failed to prune processed events: {:?}", e),
+        }
+        tokio::select! {
+            _ = shutdown.cancelled() => {}
+            _ = tokio::time::sleep(Duration::from_secs(3600)) => {}
+        }
+    }
+}
+
+/// prune_orphan_events
+/// Periodically deletes orphan events older than the retention period until shutdown
+pub async fn prune_orphan_events(
+    db_pool: SqlitePool,
+    retention_days: i64,
+    shutdown: CancellationToken,
+) {
+    while !shutdown.is_cancelled() {
+        match OrphanEvent::prune(&db_pool, retention_days).await {
+            Ok(0) => {}
+            Ok(pruned) => println!("Pruned {} orphan events", pruned),
+            Err(e) => eprintln!("Failed to prune orphan events: {:?}", e),
+        }
+        tokio::select! {
+            _ = shutdown.cancelled() => {}
+            _ = tokio::time::sleep(Duration::from_secs(3600)) => {}
+        }
+    }
+}
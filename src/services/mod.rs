@@ -1,3 +1,10 @@
+pub mod events;
+pub mod monitor;
 pub mod receiver;
+pub mod renderer;
 pub mod scheduler;
 pub mod sender;
+pub mod sns;
+pub mod sqs;
+pub mod tracking;
+pub mod webhook;
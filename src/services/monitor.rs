@@ -0,0 +1,372 @@
+use crate::config;
+use crate::models::result::{DeliveryStats, EmailResult};
+use crate::models::topic::Topic;
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// PauseInfo
+/// Why and when sending was paused
+#[derive(Serialize, Clone, Debug)]
+pub struct PauseInfo {
+    pub reason: String,
+    pub paused_at: String,
+}
+
+/// SendingPause
+/// Shared switch that holds back every send while set
+/// Requests stay in the outbox (Created) until sending is resumed
+/// The state is stored with `persist` and restored with `load`, so a pause survives restarts
+#[derive(Clone, Default)]
+pub struct SendingPause {
+    inner: Arc<RwLock<Option<PauseInfo>>>,
+}
+
+impl SendingPause {
+    /// pause
+    /// Pause sending; returns false if it was already paused
+    pub fn pause(&self, reason: &str) -> bool {
+        let mut inner = self.inner.write().expect("Pause lock poisoned");
+        if inner.is_some() {
+            return false;
+        }
+        *inner = Some(PauseInfo {
+            reason: reason.to_string(),
+            paused_at: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        });
+        true
+    }
+
+    /// resume
+    /// Resume sending; returns false if it was not paused
+    pub fn resume(&self) -> bool {
+        self.inner
+            .write()
+            .expect("Pause lock poisoned")
+            .take()
+            .is_some()
+    }
+
+    /// is_paused
+    pub fn is_paused(&self) -> bool {
+        self.inner.read().expect("Pause lock poisoned").is_some()
+    }
+
+    /// info
+    /// Returns the pause reason and time, if paused
+    pub fn info(&self) -> Option<PauseInfo> {
+        self.inner.read().expect("Pause lock poisoned").clone()
+    }
+
+    /// load
+    /// Restores the pause stored by a previous process
+    pub async fn load(db_pool: &SqlitePool) -> Result<Self, sqlx::Error> {
+        let info = sqlx::query_as!(
+            PauseInfo,
+            r#"
+            SELECT reason, paused_at as "paused_at!: String"
+            FROM sending_pause
+            WHERE id = 1
+            "#,
+        )
+        .fetch_optional(db_pool)
+        .await?;
+        Ok(Self {
+            inner: Arc::new(RwLock::new(info)),
+        })
+    }
+
+    /// persist
+    /// Stores the current state; called after every pause or resume
+    pub async fn persist(&self, db_pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        match self.info() {
+            Some(info) => {
+                sqlx::query!(
+                    r#"
+                    INSERT INTO sending_pause (id, reason, paused_at)
+                    VALUES (1, ?, ?)
+                    ON CONFLICT (id) DO UPDATE
+                    SET reason = excluded.reason,
+                        paused_at = excluded.paused_at
+                    "#,
+                    info.reason,
+                    info.paused_at,
+                )
+                .execute(db_pool)
+                .await?;
+            }
+            None => {
+                sqlx::query!("DELETE FROM sending_pause")
+                    .execute(db_pool)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// RateThresholds
+/// Limits applied to the rolling bounce and complaint rates
+#[derive(Clone, Debug)]
+pub struct RateThresholds {
+    pub bounce_rate: f64,
+    pub complaint_rate: f64,
+    pub window_minutes: i64,
+    pub min_sent: i64,
+    pub pause_topics: bool,
+}
+
+impl RateThresholds {
+    /// from_environments
+    /// Reads the thresholds from the configuration
+    pub fn from_environments() -> Self {
+        let envs = config::get_environments();
+        Self {
+            bounce_rate: envs.bounce_rate_threshold,
+            complaint_rate: envs.complaint_rate_threshold,
+            window_minutes: envs.rate_guard_window_minutes,
+            min_sent: envs.rate_guard_min_sent,
+            pause_topics: envs.rate_guard_pause_topics,
+        }
+    }
+
+    /// exceeded
+    /// Returns a description of the exceeded threshold, if any
+    /// Samples smaller than `min_sent` are not judged
+    fn exceeded(&self, stats: &DeliveryStats) -> Option<String> {
+        if stats.sent == 0 || stats.sent < self.min_sent {
+            return None;
+        }
+        let bounce_rate = stats.bounces as f64 / stats.sent as f64;
+        let complaint_rate = stats.complaints as f64 / stats.sent as f64;
+        if bounce_rate >= self.bounce_rate {
+            Some(format!(
+                "bounce rate {:.2}% ({}/{}) exceeds {:.2}%",
+                bounce_rate * 100.0,
+                stats.bounces,
+                stats.sent,
+                self.bounce_rate * 100.0
+            ))
+        } else if complaint_rate >= self.complaint_rate {
+            Some(format!(
+                "complaint rate {:.3}% ({}/{}) exceeds {:.3}%",
+                complaint_rate * 100.0,
+                stats.complaints,
+                stats.sent,
+                self.complaint_rate * 100.0
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// total_stats
+/// Sums the per-topic stats into account-wide stats
+pub fn total_stats(stats: &[DeliveryStats]) -> DeliveryStats {
+    stats
+        .iter()
+        .fold(DeliveryStats::default(), |total, topic| DeliveryStats {
+            topic_id: total.topic_id,
+            sent: total.sent + topic.sent,
+            bounces: total.bounces + topic.bounces,
+            complaints: total.complaints + topic.complaints,
+        })
+}
+
+/// RateGuard
+/// Pauses sending or topics whose bounce/complaint rates are too high
+pub struct RateGuard {
+    thresholds: RateThresholds,
+}
+
+impl RateGuard {
+    /// new
+    pub fn new(thresholds: RateThresholds) -> Self {
+        Self { thresholds }
+    }
+
+    /// check
+    /// Evaluates the rolling rates once
+    /// Topics over the limit are paused (if enabled) once per breach; the sender is paused if the account-wide rate is over the limit
+    pub async fn check(
+        &self,
+        db_pool: &SqlitePool,
+        pause: &SendingPause,
+    ) -> Result<(), sqlx::Error> {
+        let stats =
+            EmailResult::get_delivery_stats_by_topic(db_pool, self.thresholds.window_minutes)
+                .await?;
+
+        if self.thresholds.pause_topics {
+            let exceeded: HashMap<&str, String> = stats
+                .iter()
+                .filter_map(|topic| {
+                    self.thresholds
+                        .exceeded(topic)
+                        .map(|exceeded| (topic.topic_id.as_str(), exceeded))
+                })
+                .collect();
+            // A topic back under the limit can be paused again by a later breach
+            for topic_id in Topic::list_breached(db_pool).await? {
+                if !exceeded.contains_key(topic_id.as_str()) {
+                    Topic::clear_breach(db_pool, &topic_id).await?;
+                }
+            }
+            for (topic_id, exceeded) in exceeded {
+                if let Some(held) = Topic::pause_for_breach(db_pool, topic_id).await? {
+                    alert(&format!(
+                        "Paused topic {} ({} requests held): {}",
+                        topic_id, held, exceeded
+                    ));
+                }
+            }
+        }
+
+        if let Some(exceeded) = self.thresholds.exceeded(&total_stats(&stats)) {
+            let reason = format!("Account {}", exceeded);
+            if pause.pause(&reason) {
+                pause.persist(db_pool).await?;
+                alert(&format!("Paused sending: {}", reason));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// alert
+/// Reports a guard action to the log and Sentry
+fn alert(message: &str) {
+    eprintln!("{}", message);
+    sentry::capture_message(message, sentry::Level::Error);
+}
+
+/// monitor_delivery_rates
+/// Background monitor that checks the rolling bounce/complaint rates every minute
+/// Stops when shutdown is signalled
+pub async fn monitor_delivery_rates(
+    db_pool: SqlitePool,
+    pause: SendingPause,
+    shutdown: CancellationToken,
+) {
+    let guard = RateGuard::new(RateThresholds::from_environments());
+    while !shutdown.is_cancelled() {
+        if let Err(e) = guard.check(&db_pool, &pause).await {
+            eprintln!("Failed to check delivery rates: {:?}", e);
+        }
+        tokio::select! {
+            _ = shutdown.cancelled() => {}
+            _ = tokio::time::sleep(Duration::from_secs(60)) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::request::{EmailMessageStatus, EmailRequest};
+
+    fn thresholds() -> RateThresholds {
+        RateThresholds {
+            bounce_rate: 0.05,
+            complaint_rate: 0.001,
+            window_minutes: 60,
+            min_sent: 10,
+            pause_topics: true,
+        }
+    }
+
+    /// Inserts `sent` sent requests for the topic, the first `bounces` of which permanently bounced
+    async fn insert_sent(db_pool: &SqlitePool, topic_id: &str, sent: i64, bounces: i64) {
+        for i in 0..sent {
+            let id: i64 = sqlx::query_scalar(
+                r#"
+                INSERT INTO email_requests (topic_id, email, subject, content, scheduled_at, status)
+                VALUES (?, 'test@example.com', 'test', 'test', datetime('now'), ?)
+                RETURNING id
+                "#,
+            )
+            .bind(topic_id)
+            .bind(EmailMessageStatus::Sent as i32)
+            .fetch_one(db_pool)
+            .await
+            .unwrap();
+            if i < bounces {
+                sqlx::query(
+                    r#"
+                    INSERT INTO email_results (request_id, status, raw, bounce_type)
+                    VALUES (?, 'Bounce', '{"bounce": {"bounceType": "Permanent"}}', 'Permanent')
+                    "#,
+                )
+                .bind(id)
+                .execute(db_pool)
+                .await
+                .unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_guard_pauses_topic_and_sender() {
+        let db_pool = crate::tests::db_pool().await;
+        insert_sent(&db_pool, "good", 10, 0).await;
+        insert_sent(&db_pool, "bad", 10, 3).await;
+        sqlx::query(
+            r#"
+            INSERT INTO email_requests (topic_id, email, subject, content, scheduled_at)
+            VALUES ('bad', 'queued@example.com', 'test', 'test', datetime('now'))
+            "#,
+        )
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        let pause = SendingPause::default();
+        let guard = RateGuard::new(thresholds());
+        guard.check(&db_pool, &pause).await.unwrap();
+
+        // 3/20 = 15% account-wide
+        assert!(pause.is_paused());
+        assert!(pause.info().unwrap().reason.contains("bounce rate"));
+        let counts = EmailRequest::get_request_counts_by_topic_id(&db_pool, "bad")
+            .await
+            .unwrap();
+        assert_eq!(counts.get("Paused"), Some(&1));
+        assert!(Topic::is_paused(&db_pool, "bad").await.unwrap());
+        assert!(!Topic::is_paused(&db_pool, "good").await.unwrap());
+
+        // A topic resumed while still over the limit is not paused again for the same breach
+        Topic::resume(&db_pool, "bad").await.unwrap();
+        guard.check(&db_pool, &pause).await.unwrap();
+        assert!(!Topic::is_paused(&db_pool, "bad").await.unwrap());
+
+        // Once back under the limit, a later breach pauses it again
+        insert_sent(&db_pool, "bad", 60, 0).await;
+        guard.check(&db_pool, &pause).await.unwrap();
+        assert!(Topic::list_breached(&db_pool).await.unwrap().is_empty());
+        insert_sent(&db_pool, "bad", 10, 10).await;
+        guard.check(&db_pool, &pause).await.unwrap();
+        assert!(Topic::is_paused(&db_pool, "bad").await.unwrap());
+        // The pause is restored after a restart
+        let restored = SendingPause::load(&db_pool).await.unwrap();
+        assert!(restored.info().unwrap().reason.contains("bounce rate"));
+        assert!(restored.resume());
+        restored.persist(&db_pool).await.unwrap();
+        assert!(!SendingPause::load(&db_pool).await.unwrap().is_paused());
+    }
+
+    #[tokio::test]
+    async fn test_rate_guard_ignores_small_samples() {
+        let db_pool = crate::tests::db_pool().await;
+        insert_sent(&db_pool, "small", 5, 5).await;
+
+        let pause = SendingPause::default();
+        let guard = RateGuard::new(thresholds());
+        guard.check(&db_pool, &pause).await.unwrap();
+        assert!(!pause.is_paused());
+    }
+}
@@ -1,42 +1,113 @@
 use crate::config;
 use crate::models::request::{EmailMessageStatus, EmailRequest};
+use crate::models::suppression::Suppression;
+use crate::services::monitor::SendingPause;
+use crate::services::sender::EmailTransport;
 use sqlx::SqlitePool;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio::sync::Mutex;
 use tokio::time::interval;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
 
 /// receive_send_message
 /// Message reception and sending
+/// While sending is paused, received messages are returned to Created
+/// On shutdown, stops taking new messages, waits for in-flight sends and returns queued ones to Created
 pub async fn receive_send_message(
     rx: &Arc<Mutex<mpsc::Receiver<EmailRequest>>>,
     tx: &mpsc::Sender<EmailRequest>,
+    transport: Arc<dyn EmailTransport>,
+    db_pool: SqlitePool,
+    pause: SendingPause,
+    shutdown: CancellationToken,
 ) {
     let envs = config::get_environments();
     let max_send_per_second = envs.max_send_per_second;
     // Consume 24 messages per second
     let mut interval = interval(Duration::from_millis(1000 / max_send_per_second as u64));
     let mut rx_guard = rx.lock().await;
+    let tracker = TaskTracker::new();
     loop {
-        interval.tick().await;
-        if let Some(mut request) = rx_guard.recv().await {
+        let received = tokio::select! {
+            biased;
+            _ = shutdown.cancelled() => break,
+            received = rx_guard.recv() => received,
+        };
+        if let Some(mut request) = received {
+            // Hold requests in the outbox until sending is resumed
+            if pause.is_paused() {
+                if let Err(e) = request.release(&db_pool).await {
+                    eprintln!("Failed to release request {:?}: {:?}", request.id, e);
+                }
+                continue;
+            }
+            // Skip requests whose claim is no longer held (re-queued, stopped or paused topic)
+            // before they take a send slot
+            match request
+                .confirm_lease(&db_pool, envs.outbox_lease_seconds)
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => {
+                    println!("Skipping request {:?}: claim no longer held", request.id);
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("Failed to confirm claim: {:?}", e);
+                    continue;
+                }
+            }
+            tokio::select! {
+                biased;
+                _ = shutdown.cancelled() => {
+                    if let Err(e) = request.release(&db_pool).await {
+                        eprintln!("Failed to release request {:?}: {:?}", request.id, e);
+                    }
+                    break;
+                }
+                _ = interval.tick() => {}
+            }
             let server_url = &envs.server_url;
+            if request.track_clicks {
+                request.content = crate::services::tracking::rewrite_links(
+                    &request.content,
+                    request.id.unwrap_or_default(),
+                    server_url,
+                );
+            }
             request.content = format!(
-                "{}<img src=\"{}/v1/events/open?request_id={}\">",
+                "{}{}",
                 request.content,
-                server_url,
-                request.id.unwrap_or_default()
+                crate::services::tracking::open_pixel(request.id.unwrap_or_default(), server_url)
             );
             let cloned_tx = tx.clone();
-            tokio::spawn(async move {
-                let send_result = crate::services::sender::send_email(
-                    &envs.aws_ses_from_email,
-                    &request.email,
-                    &request.subject,
-                    &request.content,
-                )
-                .await;
+            let transport = Arc::clone(&transport);
+            let db_pool = db_pool.clone();
+            tracker.spawn(async move {
+                // Recipients suppressed after the request was queued are not sent
+                match Suppression::is_suppressed(&db_pool, &request.email).await {
+                    Ok(true) => {
+                        request.status = EmailMessageStatus::Suppressed as i32;
+                        request.error = Some("Recipient is suppressed".to_string());
+                        if let Err(e) = cloned_tx.send(request).await {
+                            eprintln!("Error sending data to channel: {:?}", e);
+                        }
+                        return;
+                    }
+                    Ok(false) => {}
+                    Err(e) => eprintln!("Failed to check suppression: {:?}", e),
+                }
+                let send_result = transport
+                    .send(
+                        &envs.aws_ses_from_email,
+                        &request.email,
+                        &request.subject,
+                        &request.content,
+                    )
+                    .await;
 
                 match send_result {
                     Ok(message_id) => {
@@ -58,16 +129,273 @@ pub async fn receive_send_message(
             break;
         }
     }
+
+    // Return requests still waiting in the channel to Created
+    rx_guard.close();
+    let mut released = 0;
+    while let Some(request) = rx_guard.recv().await {
+        if let Err(e) = request.release(&db_pool).await {
+            eprintln!("Failed to release request {:?}: {:?}", request.id, e);
+        } else {
+            released += 1;
+        }
+    }
+    if released > 0 {
+        println!("Released {} unsent requests", released);
+    }
+
+    // Let in-flight sends finish
+    tracker.close();
+    tracker.wait().await;
 }
 
 /// receive_post_send_message
 /// Update the database with received message results
+/// Runs until every sender is dropped, so pending updates are flushed on shutdown
 pub async fn receive_post_send_message(
     rx: &Arc<Mutex<mpsc::Receiver<EmailRequest>>>,
     db_pool: SqlitePool,
 ) {
     let mut rx_guard = rx.lock().await;
     while let Some(request) = rx_guard.recv().await {
-        request.update(&db_pool).await;
+        let id = request.id;
+        if let Err(e) = request.update(&db_pool).await {
+            // The lease expires and the request is re-queued
+            eprintln!("Failed to update request {:?}: {:?}", id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::sender::SinkTransport;
+
+    async fn save_request(db_pool: &SqlitePool, email: &str) -> EmailRequest {
+        EmailRequest {
+            id: None,
+            topic_id: Some("topic".to_string()),
+            email: email.to_string(),
+            subject: "subject".to_string(),
+            content: "content".to_string(),
+            scheduled_at: None,
+            status: EmailMessageStatus::Processed as i32,
+            error: None,
+            message_id: None,
+            leased_until: None,
+            lease_id: None,
+            template_id: None,
+            template_version: None,
+            track_clicks: true,
+        }
+        .save(db_pool, 60, None)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_receive_send_message_uses_transport() {
+        crate::tests::set_test_environments();
+        let db_pool = crate::tests::db_pool().await;
+        let (tx_send, rx_send) = mpsc::channel(10);
+        let (tx_post_send, mut rx_post_send) = mpsc::channel(10);
+        let transport = Arc::new(SinkTransport::new(None));
+
+        let rx_send = Arc::new(Mutex::new(rx_send));
+        let cloned_transport: Arc<dyn EmailTransport> = transport.clone();
+        let cloned_db_pool = db_pool.clone();
+        tokio::spawn(async move {
+            receive_send_message(
+                &rx_send,
+                &tx_post_send,
+                cloned_transport,
+                cloned_db_pool,
+                SendingPause::default(),
+                CancellationToken::new(),
+            )
+            .await;
+        });
+
+        tx_send
+            .send(save_request(&db_pool, "a@example.com").await)
+            .await
+            .unwrap();
+        tx_send
+            .send(save_request(&db_pool, "b@example.com").await)
+            .await
+            .unwrap();
+
+        let mut results = vec![
+            rx_post_send.recv().await.unwrap(),
+            rx_post_send.recv().await.unwrap(),
+        ];
+        results.sort_by_key(|r| r.id);
+        for result in &results {
+            assert!(result.status == EmailMessageStatus::Sent as i32);
+            assert!(result.message_id.as_deref().unwrap().starts_with("sink-"));
+        }
+
+        let sent = transport.sent().await;
+        assert_eq!(sent.len(), 2);
+        assert!(sent[0].body.contains("/v1/events/open?token="));
+    }
+
+    #[tokio::test]
+    async fn test_receive_send_message_skips_lost_claim() {
+        // A request that was re-queued after its lease expired must not be sent by the stale copy
+        crate::tests::set_test_environments();
+        let db_pool = crate::tests::db_pool().await;
+        let (tx_send, rx_send) = mpsc::channel(10);
+        let (tx_post_send, mut rx_post_send) = mpsc::channel(10);
+        let transport = Arc::new(SinkTransport::new(None));
+
+        let stale = save_request(&db_pool, "stale@example.com").await;
+        sqlx::query("UPDATE email_requests SET leased_until = datetime('now', '-1 minutes')")
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        EmailRequest::requeue_expired(&db_pool).await.unwrap();
+
+        let rx_send = Arc::new(Mutex::new(rx_send));
+        let cloned_transport: Arc<dyn EmailTransport> = transport.clone();
+        let cloned_db_pool = db_pool.clone();
+        tokio::spawn(async move {
+            receive_send_message(
+                &rx_send,
+                &tx_post_send,
+                cloned_transport,
+                cloned_db_pool,
+                SendingPause::default(),
+                CancellationToken::new(),
+            )
+            .await;
+        });
+        tx_send.send(stale).await.unwrap();
+        tx_send
+            .send(save_request(&db_pool, "fresh@example.com").await)
+            .await
+            .unwrap();
+
+        let result = rx_post_send.recv().await.unwrap();
+        assert_eq!(result.email, "fresh@example.com");
+        let sent = transport.sent().await;
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].recipient, "fresh@example.com");
+    }
+
+    #[tokio::test]
+    async fn test_receive_send_message_releases_queued_on_shutdown() {
+        // Requests still waiting in the channel at shutdown go back to Created
+        crate::tests::set_test_environments();
+        let db_pool = crate::tests::db_pool().await;
+        let (tx_send, rx_send) = mpsc::channel(10);
+        let (tx_post_send, mut rx_post_send) = mpsc::channel(10);
+        let transport = Arc::new(SinkTransport::new(None));
+
+        tx_send
+            .send(save_request(&db_pool, "queued@example.com").await)
+            .await
+            .unwrap();
+        let shutdown = CancellationToken::new();
+        shutdown.cancel();
+
+        let rx_send = Arc::new(Mutex::new(rx_send));
+        let cloned_transport: Arc<dyn EmailTransport> = transport.clone();
+        receive_send_message(
+            &rx_send,
+            &tx_post_send,
+            cloned_transport,
+            db_pool.clone(),
+            SendingPause::default(),
+            shutdown,
+        )
+        .await;
+        drop(tx_post_send);
+
+        assert!(rx_post_send.recv().await.is_none());
+        assert!(transport.sent().await.is_empty());
+        let status: i64 = sqlx::query_scalar("SELECT status FROM email_requests")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(status, EmailMessageStatus::Created as i64);
+    }
+
+    #[tokio::test]
+    async fn test_receive_send_message_skips_suppressed_recipient() {
+        // A recipient suppressed after the request was queued is not sent
+        crate::tests::set_test_environments();
+        let db_pool = crate::tests::db_pool().await;
+        let (tx_send, rx_send) = mpsc::channel(10);
+        let (tx_post_send, mut rx_post_send) = mpsc::channel(10);
+        let transport = Arc::new(SinkTransport::new(None));
+
+        let request = save_request(&db_pool, "bounced@example.com").await;
+        Suppression {
+            id: None,
+            email: "bounced@example.com".to_string(),
+            reason: "Manual".to_string(),
+            source: "api".to_string(),
+            created_at: None,
+        }
+        .save(&db_pool)
+        .await
+        .unwrap();
+
+        let rx_send = Arc::new(Mutex::new(rx_send));
+        let cloned_transport: Arc<dyn EmailTransport> = transport.clone();
+        let cloned_db_pool = db_pool.clone();
+        tokio::spawn(async move {
+            receive_send_message(
+                &rx_send,
+                &tx_post_send,
+                cloned_transport,
+                cloned_db_pool,
+                SendingPause::default(),
+                CancellationToken::new(),
+            )
+            .await;
+        });
+        tx_send.send(request).await.unwrap();
+
+        let result = rx_post_send.recv().await.unwrap();
+        assert!(result.status == EmailMessageStatus::Suppressed as i32);
+        assert!(transport.sent().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_receive_send_message_holds_requests_while_paused() {
+        // Requests received while paused are not sent and go back to Created
+        crate::tests::set_test_environments();
+        let db_pool = crate::tests::db_pool().await;
+        let (tx_send, rx_send) = mpsc::channel(10);
+        let (tx_post_send, _rx_post_send) = mpsc::channel(10);
+        let transport = Arc::new(SinkTransport::new(None));
+        let pause = SendingPause::default();
+        pause.pause("test");
+
+        tx_send
+            .send(save_request(&db_pool, "paused@example.com").await)
+            .await
+            .unwrap();
+        drop(tx_send);
+        let rx_send = Arc::new(Mutex::new(rx_send));
+        let cloned_transport: Arc<dyn EmailTransport> = transport.clone();
+        receive_send_message(
+            &rx_send,
+            &tx_post_send,
+            cloned_transport,
+            db_pool.clone(),
+            pause,
+            CancellationToken::new(),
+        )
+        .await;
+
+        assert!(transport.sent().await.is_empty());
+        let status: i64 = sqlx::query_scalar("SELECT status FROM email_requests")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(status, EmailMessageStatus::Created as i64);
     }
 }
@@ -0,0 +1,85 @@
+use handlebars::{no_escape, Handlebars};
+use serde_json::Value;
+
+/// MessageTemplate
+/// Subject and content compiled once and rendered per recipient
+/// Content is HTML-escaped; the subject is plain text and is not escaped
+pub struct MessageTemplate {
+    subject: Handlebars<'static>,
+    content: Handlebars<'static>,
+}
+
+impl MessageTemplate {
+    /// new
+    /// Compiles the subject and content templates
+    pub fn new(subject: &str, content: &str) -> Result<Self, String> {
+        let mut subject_registry = Handlebars::new();
+        subject_registry.set_strict_mode(true);
+        subject_registry.register_escape_fn(no_escape);
+        subject_registry
+            .register_template_string("subject", subject)
+            .map_err(|e| format!("Invalid subject template: {}", e))?;
+
+        let mut content_registry = Handlebars::new();
+        content_registry.set_strict_mode(true);
+        content_registry
+            .register_template_string("content", content)
+            .map_err(|e| format!("Invalid content template: {}", e))?;
+
+        Ok(Self {
+            subject: subject_registry,
+            content: content_registry,
+        })
+    }
+
+    /// render
+    /// Renders the subject and content with the recipient's variables
+    /// Missing variables are reported as errors
+    pub fn render(&self, vars: &Value) -> Result<(String, String), String> {
+        let subject = self
+            .subject
+            .render("subject", vars)
+            .map_err(|e| format!("Failed to render subject: {}", e))?;
+        let content = self
+            .content
+            .render("content", vars)
+            .map_err(|e| format!("Failed to render content: {}", e))?;
+        Ok((subject, content))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_with_vars() {
+        let template = MessageTemplate::new("Hi {{first_name}}", "<p>Hello {{first_name}}</p>")
+            .expect("Failed to compile template");
+        let (subject, content) = template
+            .render(&json!({"first_name": "Jane"}))
+            .expect("Failed to render template");
+        assert_eq!(subject, "Hi Jane");
+        assert_eq!(content, "<p>Hello Jane</p>");
+    }
+
+    #[test]
+    fn test_render_escapes_content_only() {
+        let template = MessageTemplate::new("{{name}}", "{{name}}").unwrap();
+        let (subject, content) = template.render(&json!({"name": "<b>&</b>"})).unwrap();
+        assert_eq!(subject, "<b>&</b>");
+        assert_eq!(content, "&lt;b&gt;&amp;&lt;/b&gt;");
+    }
+
+    #[test]
+    fn test_render_missing_var_fails() {
+        let template = MessageTemplate::new("Hi {{first_name}}", "body").unwrap();
+        assert!(template.render(&json!({})).is_err());
+    }
+
+    #[test]
+    fn test_invalid_template_fails() {
+        assert!(MessageTemplate::new("Hi {{#if}}", "body").is_err());
+    }
+}
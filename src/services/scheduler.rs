@@ -1,78 +1,109 @@
-use crate::models::request::{EmailMessageStatus, EmailRequest};
+use crate::config;
+use crate::models::request::EmailRequest;
+use crate::services::monitor::SendingPause;
 use sqlx::SqlitePool;
 use std::time::Duration;
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+/// CLAIM_BATCH_SIZE
+/// Most requests claimed per pass
+const CLAIM_BATCH_SIZE: usize = 1000;
+
+/// lease_for_queue
+/// Lease long enough for a request to wait behind `queued` others at `send_per_second`,
+/// plus `lease_seconds` for the send itself
+fn lease_for_queue(queued: usize, send_per_second: i32, lease_seconds: i64) -> i64 {
+    let send_per_second = send_per_second.max(1) as i64;
+    (queued as i64 + send_per_second - 1) / send_per_second + lease_seconds
+}
 
 /// schedule_pre_send_message
 /// Scheduler for sending scheduled messages
-/// Queries valid messages in batches of 1000 based on the current time and sends them
-pub async fn schedule_pre_send_message(tx: &mpsc::Sender<EmailRequest>, db_pool: SqlitePool) {
-    loop {
-        match sqlx::query!(
-            "SELECT id, topic_id, email, subject, content \
-             FROM email_requests \
-             WHERE status = 0 AND scheduled_at <= datetime('now') \
-             LIMIT 1000"
-        )
-        .fetch_all(&db_pool)
-        .await
-        {
-            Ok(rows) => {
-                if rows.is_empty() {
-                    println!("No data to send");
-                    tokio::time::sleep(Duration::from_secs(60)).await;
-                    continue;
-                }
+/// Claims due messages in batches of up to 1000, never more than the channel has room for,
+/// so claims do not expire while they wait in the channel
+/// Claims whose lease has expired are returned to the queue first, so the DB stays the source of truth
+/// Nothing is claimed while sending is paused
+/// Stops when shutdown is signalled
+pub async fn schedule_pre_send_message(
+    tx: &mpsc::Sender<EmailRequest>,
+    db_pool: SqlitePool,
+    pause: SendingPause,
+    shutdown: CancellationToken,
+) {
+    let envs = config::get_environments();
+    while !shutdown.is_cancelled() {
+        match EmailRequest::requeue_expired(&db_pool).await {
+            Ok(0) => {}
+            Ok(count) => println!("Re-queued {} expired claims", count),
+            Err(e) => eprintln!("Failed to re-queue expired claims: {:?}", e),
+        }
 
-                // 1. Collect ids of the retrieved data and update their status
-                let ids: Vec<i32> = rows
-                    .iter()
-                    .filter_map(|row| row.id)
-                    .map(|id| id as i32)
-                    .collect();
+        // Leave due requests in the outbox while sending is paused
+        if pause.is_paused() {
+            println!("Sending is paused");
+            sleep_or_shutdown(&shutdown).await;
+            continue;
+        }
 
-                if !ids.is_empty() {
-                    let ids_str = ids
-                        .iter()
-                        .map(|id| id.to_string())
-                        .collect::<Vec<_>>()
-                        .join(",");
-                    let update_sql = format!(
-                        "UPDATE email_requests SET status = 1 WHERE id IN ({})",
-                        ids_str
-                    );
-                    if let Err(e) = sqlx::query(&update_sql).execute(&db_pool).await {
-                        eprintln!("Failed to update status: {:?}", e);
-                        continue;
-                    }
+        // Wait for room in the channel instead of claiming requests that cannot be queued yet
+        let batch_size = tx.capacity().min(CLAIM_BATCH_SIZE);
+        if batch_size == 0 {
+            tokio::select! {
+                _ = shutdown.cancelled() => {}
+                _ = tokio::time::sleep(Duration::from_secs(1)) => {}
+            }
+            continue;
+        }
+        // The last request of the batch waits behind everything already queued
+        let queued = tx.max_capacity() - tx.capacity() + batch_size;
+        let lease_seconds =
+            lease_for_queue(queued, envs.max_send_per_second, envs.outbox_lease_seconds);
+
+        match EmailRequest::claim_due(&db_pool, batch_size as i64, lease_seconds).await {
+            Ok(requests) => {
+                if requests.is_empty() {
+                    println!("No data to send");
+                    sleep_or_shutdown(&shutdown).await;
+                    continue;
                 }
 
-                for row in rows {
-                    let id = row.id.unwrap_or(0) as i32;
-                    let topic_id = row.topic_id;
-                    let email = row.email;
-                    let subject = row.subject;
-                    let content = row.content;
-                    let request = EmailRequest {
-                        id: Some(id),
-                        topic_id: Some(topic_id),
-                        email,
-                        subject,
-                        content,
-                        // Unused value (initialization only)
-                        scheduled_at: None,
-                        status: EmailMessageStatus::Created as i32,
-                        error: None,
-                        message_id: None,
-                    };
+                for request in requests {
                     if let Err(e) = tx.send(request).await {
                         eprintln!("Failed to send data to channel: {:?}", e);
+                        // Return the claim so it is not left Processed
+                        if let Err(e) = e.0.release(&db_pool).await {
+                            eprintln!("Failed to release request: {:?}", e);
+                        }
                     }
                 }
             }
             Err(e) => {
                 eprintln!("Error fetching events: {:?}", e);
+                sleep_or_shutdown(&shutdown).await;
             }
         }
     }
 }
+
+/// sleep_or_shutdown
+/// Wait for the next polling cycle, returning early on shutdown
+async fn sleep_or_shutdown(shutdown: &CancellationToken) {
+    tokio::select! {
+        _ = shutdown.cancelled() => {}
+        _ = tokio::time::sleep(Duration::from_secs(60)) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lease_for_queue_covers_queue_wait() {
+        // 11000 queued requests at 12/s take 917 seconds to drain
+        assert_eq!(lease_for_queue(11_000, 12, 900), 917 + 900);
+        assert_eq!(lease_for_queue(0, 12, 900), 900);
+        assert_eq!(lease_for_queue(10, 0, 900), 910);
+    }
+}
@@ -1,60 +1,284 @@
 use crate::config;
+use async_trait::async_trait;
 use aws_config::meta::region::RegionProviderChain;
 use aws_config::BehaviorVersion;
 use aws_sdk_sesv2::types::{Body, Content, Destination, EmailContent, Message};
 use aws_sdk_sesv2::{config::Region, Client};
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
 
-/// send_email
+/// TransportError
+/// Error returned by an email transport
+pub type TransportError = Box<dyn std::error::Error + Send + Sync>;
+
+/// EmailTransport
+/// Transport used to deliver a single email
+/// Returns the message ID assigned by the transport
+#[async_trait]
+pub trait EmailTransport: Send + Sync {
+    async fn send(
+        &self,
+        sender: &str,
+        recipient: &str,
+        subject: &str,
+        body: &str,
+    ) -> Result<String, TransportError>;
+}
+
+/// build_transport
+/// Creates the email transport selected by EMAIL_TRANSPORT (ses, smtp, sink)
+/// Built once at startup and shared by every sending task
+/// Unknown values are an error, so a typo never falls back to sending through SES
+pub async fn build_transport(
+    envs: &config::Environment,
+) -> Result<Arc<dyn EmailTransport>, String> {
+    match envs.email_transport.as_str() {
+        "ses" => Ok(Arc::new(SesTransport::new(&envs.aws_region).await)),
+        "smtp" => Ok(Arc::new(SmtpTransport::new(envs)?)),
+        "sink" => Ok(Arc::new(SinkTransport::new(envs.email_sink_path.clone()))),
+        other => Err(format!(
+            "Unknown EMAIL_TRANSPORT \"{}\" (expected ses, smtp or sink)",
+            other
+        )),
+    }
+}
+
+/// SesTransport
 /// Send email using AWS SES
 /// Environment variables AWS_ACCESS_KEY_ID, AWS_SECRET_ACCESS_KEY, AWS_REGION are required for sending
-pub async fn send_email(
-    sender: &str,
-    recipient: &str,
-    subject: &str,
-    body: &str,
-) -> Result<String, aws_sdk_sesv2::Error> {
-    let envs = config::get_environments();
-    let aws_region = &envs.aws_region;
-    let region_provider = RegionProviderChain::first_try(Region::new(aws_region))
-        .or_default_provider()
-        .or_else(Region::new(aws_region));
-
-    let shared_config = aws_config::defaults(BehaviorVersion::latest())
-        .region(region_provider)
-        .load()
-        .await;
-
-    let client = Client::new(&shared_config);
-    let message = Message::builder()
-        .subject(
-            Content::builder()
-                .data(subject)
-                .charset("UTF-8") // Using UTF-8 encoding
-                .build()
-                .expect("Failed to build subject content"),
-        )
-        .body(
-            Body::builder()
-                .html(
-                    // Convert to HTML format
-                    Content::builder()
-                        .data(body)
-                        .charset("UTF-8")
-                        .build()
-                        .expect("Failed to build body content"),
+/// The client is created once; credential refresh is handled by the SDK
+pub struct SesTransport {
+    client: Client,
+}
+
+impl SesTransport {
+    pub async fn new(aws_region: &str) -> Self {
+        let region_provider = RegionProviderChain::first_try(Region::new(aws_region.to_string()))
+            .or_default_provider()
+            .or_else(Region::new(aws_region.to_string()));
+
+        let shared_config = aws_config::defaults(BehaviorVersion::latest())
+            .region(region_provider)
+            .load()
+            .await;
+
+        Self {
+            client: Client::new(&shared_config),
+        }
+    }
+}
+
+#[async_trait]
+impl EmailTransport for SesTransport {
+    async fn send(
+        &self,
+        sender: &str,
+        recipient: &str,
+        subject: &str,
+        body: &str,
+    ) -> Result<String, TransportError> {
+        let message = Message::builder()
+            .subject(
+                Content::builder()
+                    .data(subject)
+                    .charset("UTF-8") // Using UTF-8 encoding
+                    .build()?,
+            )
+            .body(
+                Body::builder()
+                    .html(
+                        // Convert to HTML format
+                        Content::builder().data(body).charset("UTF-8").build()?,
+                    )
+                    .build(),
+            )
+            .build();
+
+        // Email send request
+        let resp = self
+            .client
+            .send_email()
+            .from_email_address(sender)
+            .destination(Destination::builder().to_addresses(recipient).build())
+            .content(EmailContent::builder().simple(message).build())
+            .send()
+            .await
+            .map_err(aws_sdk_sesv2::Error::from)?;
+
+        Ok(resp.message_id().unwrap_or_default().to_string()) // Return MessageId
+    }
+}
+
+/// SmtpTransport
+/// Send email through an SMTP server (e.g. a local SMTP catcher in staging/CI)
+pub struct SmtpTransport {
+    mailer: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpTransport {
+    pub fn new(envs: &config::Environment) -> Result<Self, String> {
+        let mut builder = if envs.smtp_starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&envs.smtp_host)
+                .map_err(|e| format!("Failed to create SMTP relay: {}", e))?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&envs.smtp_host)
+        }
+        .port(envs.smtp_port);
+        if !envs.smtp_username.is_empty() {
+            builder = builder.credentials(Credentials::new(
+                envs.smtp_username.clone(),
+                envs.smtp_password.clone(),
+            ));
+        }
+        Ok(Self {
+            mailer: builder.build(),
+        })
+    }
+}
+
+#[async_trait]
+impl EmailTransport for SmtpTransport {
+    async fn send(
+        &self,
+        sender: &str,
+        recipient: &str,
+        subject: &str,
+        body: &str,
+    ) -> Result<String, TransportError> {
+        let message = lettre::Message::builder()
+            .from(sender.parse()?)
+            .to(recipient.parse()?)
+            .subject(subject)
+            .header(ContentType::TEXT_HTML)
+            .body(body.to_string())?;
+        let message_id = message
+            .headers()
+            .get_raw("Message-ID")
+            .map(|id| id.trim_matches(|c| c == '<' || c == '>').to_string())
+            .unwrap_or_default();
+        self.mailer.send(message).await?;
+        Ok(message_id)
+    }
+}
+
+/// SentEmail
+/// Email captured by the sink transport
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SentEmail {
+    pub message_id: String,
+    pub sender: String,
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// SinkTransport
+/// Appends sent emails to a file as JSON lines, or keeps them in memory when no path is set
+pub struct SinkTransport {
+    path: Option<String>,
+    sent: Mutex<Vec<SentEmail>>,
+    counter: AtomicU64,
+}
+
+impl SinkTransport {
+    pub fn new(path: Option<String>) -> Self {
+        Self {
+            path,
+            sent: Mutex::new(Vec::new()),
+            counter: AtomicU64::new(0),
+        }
+    }
+
+    /// sent
+    /// Returns the emails captured in memory so far
+    #[cfg(test)]
+    pub async fn sent(&self) -> Vec<SentEmail> {
+        self.sent.lock().await.clone()
+    }
+}
+
+#[async_trait]
+impl EmailTransport for SinkTransport {
+    async fn send(
+        &self,
+        sender: &str,
+        recipient: &str,
+        subject: &str,
+        body: &str,
+    ) -> Result<String, TransportError> {
+        let email = SentEmail {
+            message_id: format!("sink-{}", self.counter.fetch_add(1, Ordering::SeqCst) + 1),
+            sender: sender.to_string(),
+            recipient: recipient.to_string(),
+            subject: subject.to_string(),
+            body: body.to_string(),
+        };
+        if let Some(path) = &self.path {
+            let mut line = serde_json::to_string(&email)?;
+            line.push('\n');
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await?;
+            file.write_all(line.as_bytes()).await?;
+            file.flush().await?;
+        } else {
+            self.sent.lock().await.push(email.clone());
+        }
+        Ok(email.message_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sink_transport_captures_email() {
+        let transport = SinkTransport::new(None);
+        let message_id = transport
+            .send("from@example.com", "to@example.com", "subject", "body")
+            .await
+            .expect("Failed to send email");
+
+        let sent = transport.sent().await;
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].message_id, message_id);
+        assert_eq!(sent[0].recipient, "to@example.com");
+        assert_eq!(sent[0].subject, "subject");
+    }
+
+    #[tokio::test]
+    async fn test_sink_transport_appends_to_file() {
+        let path = std::env::temp_dir().join(format!("sink-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let transport = SinkTransport::new(Some(path.to_string_lossy().to_string()));
+        for i in 0..2 {
+            transport
+                .send(
+                    "from@example.com",
+                    &format!("to{}@example.com", i),
+                    "s",
+                    "b",
                 )
-                .build(),
-        )
-        .build();
-
-    // Email send request
-    let resp = client
-        .send_email()
-        .from_email_address(sender)
-        .destination(Destination::builder().to_addresses(recipient).build())
-        .content(EmailContent::builder().simple(message).build())
-        .send()
-        .await?;
-
-    Ok(resp.message_id().unwrap_or_default().to_string()) // Return MessageId
+                .await
+                .expect("Failed to send email");
+        }
+
+        let contents = std::fs::read_to_string(&path).expect("Failed to read sink file");
+        let lines: Vec<SentEmail> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].recipient, "to1@example.com");
+        let _ = std::fs::remove_file(&path);
+    }
 }
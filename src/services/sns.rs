@@ -0,0 +1,452 @@
+use crate::models::subscription::{SnsSubscription, SubscriptionStatus};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Public};
+use openssl::sign::Verifier;
+use openssl::x509::X509;
+use regex::Regex;
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::{Arc, RwLock};
+
+/// FetchError
+/// Error returned by an HTTP fetcher
+pub type FetchError = Box<dyn Error + Send + Sync>;
+
+/// HttpFetcher
+/// Fetches a URL over HTTP(S)
+/// Injected so that tests can serve local certificates instead of calling AWS
+#[async_trait]
+pub trait HttpFetcher: Send + Sync {
+    async fn get(&self, url: &str) -> Result<Vec<u8>, FetchError>;
+}
+
+/// ReqwestFetcher
+/// HTTP fetcher backed by reqwest
+#[derive(Default)]
+pub struct ReqwestFetcher {
+    client: reqwest::Client,
+}
+
+#[async_trait]
+impl HttpFetcher for ReqwestFetcher {
+    async fn get(&self, url: &str) -> Result<Vec<u8>, FetchError> {
+        let response = self.client.get(url).send().await?.error_for_status()?;
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+/// AWS_SNS_HOST_REGEX
+/// Hosts AWS serves SNS signing certificates from (sns.<region>.amazonaws.com[.cn])
+static AWS_SNS_HOST_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^sns\.[a-z0-9-]+\.amazonaws\.com(\.cn)?$")
+        .expect("Failed to compile SNS host regex")
+});
+
+/// is_sns_url
+/// Only https URLs of SNS hosts are accepted
+/// `allowed_hosts` replaces the default AWS SNS host pattern when not empty
+pub fn is_sns_url(url: &str, allowed_hosts: &[String]) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let allowed_host = if allowed_hosts.is_empty() {
+        AWS_SNS_HOST_REGEX.is_match(host)
+    } else {
+        allowed_hosts.iter().any(|allowed| allowed == host)
+    };
+    url.scheme() == "https" && allowed_host
+}
+
+/// ConfirmOutcome
+/// Result of an automatic subscription confirmation
+#[derive(Debug, PartialEq, Eq)]
+pub enum ConfirmOutcome {
+    Confirmed,
+    NotAllowed(String),
+    Failed(String),
+}
+
+/// confirm_subscription
+/// Confirms the subscription by visiting its SubscribeURL if the topic is allowed
+/// The attempt is recorded as confirmed or pending (with the reason)
+pub async fn confirm_subscription(
+    db_pool: &SqlitePool,
+    fetcher: &dyn HttpFetcher,
+    allowed_topic_arns: &[String],
+    allowed_hosts: &[String],
+    topic_arn: &str,
+    subscribe_url: &str,
+) -> Result<ConfirmOutcome, sqlx::Error> {
+    let outcome = if !allowed_topic_arns.iter().any(|arn| arn == topic_arn) {
+        ConfirmOutcome::NotAllowed("Topic ARN is not allowed".to_string())
+    } else if !is_sns_url(subscribe_url, allowed_hosts) {
+        ConfirmOutcome::NotAllowed("SubscribeURL is not an SNS URL".to_string())
+    } else {
+        match fetcher.get(subscribe_url).await {
+            Ok(_) => ConfirmOutcome::Confirmed,
+            Err(e) => ConfirmOutcome::Failed(format!("Failed to confirm subscription: {}", e)),
+        }
+    };
+    let (status, error) = match &outcome {
+        ConfirmOutcome::Confirmed => (SubscriptionStatus::Confirmed, None),
+        ConfirmOutcome::NotAllowed(e) | ConfirmOutcome::Failed(e) => {
+            (SubscriptionStatus::Pending, Some(e.clone()))
+        }
+    };
+    SnsSubscription::save(db_pool, topic_arn, status, error).await?;
+    Ok(outcome)
+}
+
+/// SnsMessage
+/// Signed SNS HTTP(S) delivery envelope
+#[derive(Deserialize, Debug, Clone)]
+pub struct SnsMessage {
+    #[serde(rename = "Type")]
+    pub message_type: String,
+    #[serde(rename = "MessageId")]
+    pub message_id: String,
+    #[serde(rename = "TopicArn")]
+    pub topic_arn: String,
+    #[serde(rename = "Subject")]
+    pub subject: Option<String>,
+    #[serde(rename = "Message")]
+    pub message: String,
+    #[serde(rename = "Timestamp")]
+    pub timestamp: String,
+    #[serde(rename = "SignatureVersion")]
+    pub signature_version: String,
+    #[serde(rename = "Signature")]
+    pub signature: String,
+    #[serde(rename = "SigningCertURL")]
+    pub signing_cert_url: String,
+    #[serde(rename = "SubscribeURL")]
+    pub subscribe_url: Option<String>,
+    #[serde(rename = "Token")]
+    pub token: Option<String>,
+}
+
+impl SnsMessage {
+    /// string_to_sign
+    /// Builds the canonical string SNS signs for the message type
+    pub fn string_to_sign(&self) -> Result<String, String> {
+        let fields: Vec<(&str, Option<&str>)> = match self.message_type.as_str() {
+            "Notification" => vec![
+                ("Message", Some(&self.message)),
+                ("MessageId", Some(&self.message_id)),
+                ("Subject", self.subject.as_deref()),
+                ("Timestamp", Some(&self.timestamp)),
+                ("TopicArn", Some(&self.topic_arn)),
+                ("Type", Some(&self.message_type)),
+            ],
+            "SubscriptionConfirmation" | "UnsubscribeConfirmation" => vec![
+                ("Message", Some(&self.message)),
+                ("MessageId", Some(&self.message_id)),
+                ("SubscribeURL", self.subscribe_url.as_deref()),
+                ("Timestamp", Some(&self.timestamp)),
+                ("Token", self.token.as_deref()),
+                ("TopicArn", Some(&self.topic_arn)),
+                ("Type", Some(&self.message_type)),
+            ],
+            other => return Err(format!("Unsupported message type: {}", other)),
+        };
+        Ok(fields
+            .into_iter()
+            .filter_map(|(key, value)| value.map(|value| format!("{}\n{}\n", key, value)))
+            .collect())
+    }
+}
+
+/// SnsVerifier
+/// Verifies SNS message signatures (SignatureVersion 1 and 2)
+/// Signing certificates are only fetched from allowed hosts and cached per URL
+pub struct SnsVerifier {
+    fetcher: Arc<dyn HttpFetcher>,
+    allowed_hosts: Vec<String>,
+    certificates: RwLock<HashMap<String, PKey<Public>>>,
+}
+
+impl SnsVerifier {
+    /// new
+    /// `allowed_hosts` replaces the default AWS SNS host pattern when not empty
+    pub fn new(fetcher: Arc<dyn HttpFetcher>, allowed_hosts: Vec<String>) -> Self {
+        Self {
+            fetcher,
+            allowed_hosts,
+            certificates: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// verify
+    /// Returns the message if its signature is valid
+    pub async fn verify(&self, body: &[u8]) -> Result<SnsMessage, String> {
+        let message: SnsMessage =
+            serde_json::from_slice(body).map_err(|e| format!("Invalid SNS message: {}", e))?;
+        let digest = match message.signature_version.as_str() {
+            "1" => MessageDigest::sha1(),
+            "2" => MessageDigest::sha256(),
+            other => return Err(format!("Unsupported SignatureVersion: {}", other)),
+        };
+        let string_to_sign = message.string_to_sign()?;
+        let signature = openssl::base64::decode_block(&message.signature)
+            .map_err(|e| format!("Invalid signature encoding: {}", e))?;
+        let public_key = self.certificate(&message.signing_cert_url).await?;
+
+        let mut verifier =
+            Verifier::new(digest, &public_key).map_err(|e| format!("Verifier error: {}", e))?;
+        verifier
+            .update(string_to_sign.as_bytes())
+            .map_err(|e| format!("Verifier error: {}", e))?;
+        match verifier.verify(&signature) {
+            Ok(true) => Ok(message),
+            _ => Err("Signature mismatch".to_string()),
+        }
+    }
+
+    /// certificate
+    /// Returns the public key of the signing certificate, fetching it on first use
+    async fn certificate(&self, url: &str) -> Result<PKey<Public>, String> {
+        if !is_sns_url(url, &self.allowed_hosts) || !url.ends_with(".pem") {
+            return Err(format!("SigningCertURL not allowed: {}", url));
+        }
+        if let Some(key) = self
+            .certificates
+            .read()
+            .expect("Certificate cache poisoned")
+            .get(url)
+        {
+            return Ok(key.clone());
+        }
+        let pem = self
+            .fetcher
+            .get(url)
+            .await
+            .map_err(|e| format!("Failed to fetch signing certificate: {}", e))?;
+        let key = X509::from_pem(&pem)
+            .and_then(|cert| cert.public_key())
+            .map_err(|e| format!("Invalid signing certificate: {}", e))?;
+        self.certificates
+            .write()
+            .expect("Certificate cache poisoned")
+            .insert(url.to_string(), key.clone());
+        Ok(key)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use openssl::asn1::Asn1Time;
+    use openssl::pkey::Private;
+    use openssl::rsa::Rsa;
+    use openssl::sign::Signer;
+    use openssl::x509::X509NameBuilder;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// CERT_URL
+    /// Signing certificate URL used by tests
+    pub const CERT_URL: &str =
+        "https://sns.ap-northeast-2.amazonaws.com/SimpleNotificationService-test.pem";
+
+    /// StaticFetcher
+    /// Serves fixed responses and counts fetches
+    pub struct StaticFetcher {
+        pub responses: HashMap<String, Vec<u8>>,
+        pub fetches: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl HttpFetcher for StaticFetcher {
+        async fn get(&self, url: &str) -> Result<Vec<u8>, FetchError> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            self.responses
+                .get(url)
+                .cloned()
+                .ok_or_else(|| format!("Not found: {}", url).into())
+        }
+    }
+
+    /// TestSigner
+    /// Self-signed certificate and key that sign SNS messages like AWS does
+    pub struct TestSigner {
+        key: PKey<Private>,
+        pub cert_pem: Vec<u8>,
+    }
+
+    impl TestSigner {
+        pub fn new() -> Self {
+            let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+            let mut name = X509NameBuilder::new().unwrap();
+            name.append_entry_by_text("CN", "sns.amazonaws.com")
+                .unwrap();
+            let name = name.build();
+            let mut builder = X509::builder().unwrap();
+            builder.set_version(2).unwrap();
+            builder.set_subject_name(&name).unwrap();
+            builder.set_issuer_name(&name).unwrap();
+            builder.set_pubkey(&key).unwrap();
+            builder
+                .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+                .unwrap();
+            builder
+                .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+                .unwrap();
+            builder.sign(&key, MessageDigest::sha256()).unwrap();
+            let cert_pem = builder.build().to_pem().unwrap();
+            Self { key, cert_pem }
+        }
+
+        /// verifier
+        /// Returns a verifier whose fetcher serves this certificate at CERT_URL
+        pub fn verifier(&self) -> (SnsVerifier, Arc<StaticFetcher>) {
+            let fetcher = Arc::new(StaticFetcher {
+                responses: HashMap::from([(CERT_URL.to_string(), self.cert_pem.clone())]),
+                fetches: AtomicUsize::new(0),
+            });
+            (SnsVerifier::new(fetcher.clone(), Vec::new()), fetcher)
+        }
+
+        /// sign
+        /// Adds SignatureVersion, Signature and SigningCertURL to an SNS message
+        pub fn sign(&self, mut body: serde_json::Value, version: &str) -> serde_json::Value {
+            body["SignatureVersion"] = version.into();
+            body["SigningCertURL"] = CERT_URL.into();
+            body["Signature"] = "".into();
+            let message: SnsMessage = serde_json::from_value(body.clone()).unwrap();
+            let digest = if version == "1" {
+                MessageDigest::sha1()
+            } else {
+                MessageDigest::sha256()
+            };
+            let mut signer = Signer::new(digest, &self.key).unwrap();
+            signer
+                .update(message.string_to_sign().unwrap().as_bytes())
+                .unwrap();
+            body["Signature"] =
+                openssl::base64::encode_block(&signer.sign_to_vec().unwrap()).into();
+            body
+        }
+    }
+
+    fn notification() -> serde_json::Value {
+        serde_json::json!({
+            "Type": "Notification",
+            "MessageId": "sns-1",
+            "TopicArn": "arn:aws:sns:ap-northeast-2:123456789012:ses-events",
+            "Message": "{\"notificationType\":\"Delivery\"}",
+            "Timestamp": "2025-01-01T00:00:00.000Z",
+        })
+    }
+
+    #[tokio::test]
+    async fn test_verify_signature_versions() {
+        let signer = TestSigner::new();
+        let (verifier, fetcher) = signer.verifier();
+        for version in ["1", "2"] {
+            let body = signer.sign(notification(), version);
+            let message = verifier.verify(body.to_string().as_bytes()).await.unwrap();
+            assert_eq!(message.message_id, "sns-1");
+        }
+        // The certificate is fetched once and cached
+        assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_verify_rejects_tampered_message() {
+        let signer = TestSigner::new();
+        let (verifier, _) = signer.verifier();
+        let mut body = signer.sign(notification(), "2");
+        body["Message"] = "{\"notificationType\":\"Bounce\"}".into();
+        assert!(verifier.verify(body.to_string().as_bytes()).await.is_err());
+        assert!(verifier
+            .verify(notification().to_string().as_bytes())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_rejects_disallowed_cert_url() {
+        let signer = TestSigner::new();
+        let (verifier, fetcher) = signer.verifier();
+        for url in [
+            "https://attacker.example.com/cert.pem",
+            "http://sns.ap-northeast-2.amazonaws.com/cert.pem",
+            "https://sns.ap-northeast-2.amazonaws.com.attacker.com/cert.pem",
+        ] {
+            let mut body = signer.sign(notification(), "2");
+            body["SigningCertURL"] = url.into();
+            assert!(verifier.verify(body.to_string().as_bytes()).await.is_err());
+        }
+        assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_confirm_subscription() {
+        let db_pool = crate::tests::db_pool().await;
+        let subscribe_url =
+            "https://sns.ap-northeast-2.amazonaws.com/?Action=ConfirmSubscription&Token=t";
+        let allowed_arn = "arn:aws:sns:ap-northeast-2:123456789012:ses-events";
+        let fetcher = StaticFetcher {
+            responses: HashMap::from([(subscribe_url.to_string(), Vec::new())]),
+            fetches: AtomicUsize::new(0),
+        };
+        let allowed = vec![allowed_arn.to_string()];
+
+        // Topics outside the allowlist are recorded as pending without visiting the URL
+        let other_arn = "arn:aws:sns:ap-northeast-2:123456789012:other";
+        let outcome =
+            confirm_subscription(&db_pool, &fetcher, &allowed, &[], other_arn, subscribe_url)
+                .await
+                .unwrap();
+        assert!(matches!(outcome, ConfirmOutcome::NotAllowed(_)));
+        assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 0);
+
+        // SubscribeURLs outside SNS are never visited
+        let outcome = confirm_subscription(
+            &db_pool,
+            &fetcher,
+            &allowed,
+            &[],
+            allowed_arn,
+            "https://attacker.example.com/confirm",
+        )
+        .await
+        .unwrap();
+        assert!(matches!(outcome, ConfirmOutcome::NotAllowed(_)));
+        assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 0);
+
+        let outcome = confirm_subscription(
+            &db_pool,
+            &fetcher,
+            &allowed,
+            &[],
+            allowed_arn,
+            subscribe_url,
+        )
+        .await
+        .unwrap();
+        assert_eq!(outcome, ConfirmOutcome::Confirmed);
+        assert_eq!(fetcher.fetches.load(Ordering::SeqCst), 1);
+
+        let subscriptions = SnsSubscription::list(&db_pool).await.unwrap();
+        assert_eq!(subscriptions.len(), 2);
+        let confirmed = subscriptions
+            .iter()
+            .find(|s| s.topic_arn == allowed_arn)
+            .unwrap();
+        assert_eq!(confirmed.status, "confirmed");
+        assert!(confirmed.error.is_none());
+        assert!(confirmed.confirmed_at.is_some());
+        let pending = subscriptions
+            .iter()
+            .find(|s| s.topic_arn == other_arn)
+            .unwrap();
+        assert_eq!(pending.status, "pending");
+    }
+}
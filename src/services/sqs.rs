@@ -0,0 +1,284 @@
+use crate::config;
+use crate::services::events;
+use async_trait::async_trait;
+use aws_config::meta::region::RegionProviderChain;
+use aws_config::BehaviorVersion;
+use aws_sdk_sqs::config::Region;
+use aws_sdk_sqs::Client;
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// QueueError
+/// Error returned by an event queue
+pub type QueueError = Box<dyn Error + Send + Sync>;
+
+/// QueueMessage
+/// Message received from an event queue
+#[derive(Clone, Debug)]
+pub struct QueueMessage {
+    pub id: String,
+    pub body: String,
+    pub receipt_handle: String,
+}
+
+/// EventQueue
+/// Queue SES/SNS events are pulled from
+/// Injected so that tests can use an in-memory queue instead of SQS
+#[async_trait]
+pub trait EventQueue: Send + Sync {
+    async fn receive(&self) -> Result<Vec<QueueMessage>, QueueError>;
+    async fn delete(&self, receipt_handle: &str) -> Result<(), QueueError>;
+}
+
+/// SqsQueue
+/// Event queue backed by Amazon SQS
+/// SQS_ENDPOINT_URL points the client at an SQS-compatible stand-in (e.g. ElasticMQ, LocalStack)
+pub struct SqsQueue {
+    client: Client,
+    queue_url: String,
+    wait_time_seconds: i32,
+    max_messages: i32,
+}
+
+impl SqsQueue {
+    pub async fn new(envs: &config::Environment, queue_url: &str) -> Self {
+        let region_provider =
+            RegionProviderChain::first_try(Region::new(envs.aws_region.to_string()))
+                .or_default_provider()
+                .or_else(Region::new(envs.aws_region.to_string()));
+
+        let shared_config = aws_config::defaults(BehaviorVersion::latest())
+            .region(region_provider)
+            .load()
+            .await;
+        let mut builder = aws_sdk_sqs::config::Builder::from(&shared_config);
+        if let Some(endpoint_url) = &envs.sqs_endpoint_url {
+            builder = builder.endpoint_url(endpoint_url);
+        }
+
+        Self {
+            client: Client::from_conf(builder.build()),
+            queue_url: queue_url.to_string(),
+            wait_time_seconds: envs.sqs_wait_time_seconds,
+            max_messages: envs.sqs_max_messages,
+        }
+    }
+}
+
+#[async_trait]
+impl EventQueue for SqsQueue {
+    async fn receive(&self) -> Result<Vec<QueueMessage>, QueueError> {
+        let output = self
+            .client
+            .receive_message()
+            .queue_url(&self.queue_url)
+            .max_number_of_messages(self.max_messages)
+            .wait_time_seconds(self.wait_time_seconds)
+            .send()
+            .await?;
+        Ok(output
+            .messages()
+            .iter()
+            .filter_map(|message| {
+                Some(QueueMessage {
+                    id: message.message_id()?.to_string(),
+                    body: message.body()?.to_string(),
+                    receipt_handle: message.receipt_handle()?.to_string(),
+                })
+            })
+            .collect())
+    }
+
+    async fn delete(&self, receipt_handle: &str) -> Result<(), QueueError> {
+        self.client
+            .delete_message()
+            .queue_url(&self.queue_url)
+            .receipt_handle(receipt_handle)
+            .send()
+            .await?;
+        Ok(())
+    }
+}
+
+/// SnsEnvelope
+/// SNS message as delivered to an SQS subscription without raw message delivery
+#[derive(Deserialize)]
+struct SnsEnvelope {
+    #[serde(rename = "Type")]
+    message_type: String,
+    #[serde(rename = "MessageId")]
+    message_id: String,
+    #[serde(rename = "Message")]
+    message: String,
+}
+
+/// handle_message
+/// Unwraps the SNS envelope and processes the notification
+/// Returns true when the message can be deleted from the queue
+async fn handle_message(db_pool: &SqlitePool, message: &QueueMessage) -> bool {
+    let (sns_message_id, notification) = match serde_json::from_str::<SnsEnvelope>(&message.body) {
+        Ok(envelope) if envelope.message_type == "Notification" => {
+            (envelope.message_id, envelope.message)
+        }
+        Ok(envelope) => {
+            // Subscriptions to SQS are confirmed by SNS itself
+            println!("Ignoring SNS {} message from SQS", envelope.message_type);
+            return true;
+        }
+        // Raw message delivery: the body is the SES notification
+        Err(_) => (message.id.clone(), message.body.clone()),
+    };
+    match events::process_notification(db_pool, &sns_message_id, notification).await {
+        Ok(_) => true,
+        Err(e) => {
+            eprintln!(
+                "Failed to process SQS message {}, leaving it for redelivery: {:?}",
+                message.id, e
+            );
+            false
+        }
+    }
+}
+
+/// poll_once
+/// Receives one batch of messages and processes them
+/// Messages are deleted only after their result is stored; the rest reappear after the visibility timeout
+/// Returns the number of deleted messages
+pub async fn poll_once(queue: &dyn EventQueue, db_pool: &SqlitePool) -> Result<usize, QueueError> {
+    let messages = queue.receive().await?;
+    let mut deleted = 0;
+    for message in messages {
+        if !handle_message(db_pool, &message).await {
+            continue;
+        }
+        match queue.delete(&message.receipt_handle).await {
+            Ok(_) => deleted += 1,
+            Err(e) => eprintln!("Failed to delete SQS message {}: {:?}", message.id, e),
+        }
+    }
+    Ok(deleted)
+}
+
+/// poll_events
+/// Long-polls the event queue until shutdown
+/// Alternative to the SNS HTTPS endpoint for environments that cannot expose one
+pub async fn poll_events(
+    queue: Arc<dyn EventQueue>,
+    db_pool: SqlitePool,
+    shutdown: CancellationToken,
+) {
+    while !shutdown.is_cancelled() {
+        let result = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            result = poll_once(queue.as_ref(), &db_pool) => result,
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to receive SQS messages: {:?}", e);
+            tokio::select! {
+                _ = shutdown.cancelled() => {}
+                _ = tokio::time::sleep(Duration::from_secs(5)) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// MemoryQueue
+    /// In-memory event queue that records deleted receipt handles
+    #[derive(Default)]
+    struct MemoryQueue {
+        messages: Mutex<Vec<QueueMessage>>,
+        deleted: Mutex<Vec<String>>,
+    }
+
+    impl MemoryQueue {
+        fn push(&self, id: &str, body: String) {
+            self.messages.lock().unwrap().push(QueueMessage {
+                id: id.to_string(),
+                body,
+                receipt_handle: format!("receipt-{}", id),
+            });
+        }
+    }
+
+    #[async_trait]
+    impl EventQueue for MemoryQueue {
+        async fn receive(&self) -> Result<Vec<QueueMessage>, QueueError> {
+            Ok(self.messages.lock().unwrap().drain(..).collect())
+        }
+
+        async fn delete(&self, receipt_handle: &str) -> Result<(), QueueError> {
+            self.deleted
+                .lock()
+                .unwrap()
+                .push(receipt_handle.to_string());
+            Ok(())
+        }
+    }
+
+    fn delivery(ses_message_id: &str) -> String {
+        serde_json::json!({
+            "notificationType": "Delivery",
+            "mail": {"messageId": ses_message_id},
+            "delivery": {"timestamp": "2025-01-01T00:00:01.000Z", "recipients": ["test@example.com"]}
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_poll_once_deletes_only_stored_messages() {
+        let db_pool = crate::tests::db_pool().await;
+        sqlx::query(
+            r#"
+            INSERT INTO email_requests (id, topic_id, email, subject, content, scheduled_at, message_id)
+            VALUES (1, 'topic_id', 'test@example.com', 'test', 'test', datetime('now'), 'ses-1');
+            "#,
+        )
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        let queue = MemoryQueue::default();
+        // SNS envelope
+        let envelope = serde_json::json!({
+            "Type": "Notification",
+            "MessageId": "sns-1",
+            "Message": delivery("ses-1"),
+        });
+        queue.push("1", envelope.to_string());
+        // Raw message delivery
+        queue.push("2", delivery("ses-1"));
+        // Not an SES notification: nothing to store
+        queue.push("3", "not json".to_string());
+        // Missing SES message ID: kept for redelivery
+        queue.push(
+            "4",
+            serde_json::json!({"notificationType": "Delivery"}).to_string(),
+        );
+
+        let deleted = poll_once(&queue, &db_pool).await.unwrap();
+        assert_eq!(deleted, 3);
+        assert_eq!(
+            *queue.deleted.lock().unwrap(),
+            vec![
+                "receipt-1".to_string(),
+                "receipt-2".to_string(),
+                "receipt-3".to_string()
+            ]
+        );
+        let count: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM email_results WHERE status = 'Delivery'")
+                .fetch_one(&db_pool)
+                .await
+                .unwrap();
+        assert_eq!(count, 2);
+    }
+}
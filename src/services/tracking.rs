@@ -0,0 +1,224 @@
+use crate::config;
+use axum::http::HeaderValue;
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// HREF_REGEX
+/// Matches absolute http(s) links in href attributes (double or single quoted)
+static HREF_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)href\s*=\s*(?:"(https?://[^"]*)"|'(https?://[^']*)')"#)
+        .expect("Failed to compile href regex")
+});
+
+/// ClickClaims
+/// Claims carried by a click tracking token
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClickClaims {
+    pub rid: i32,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
+}
+
+/// OpenClaims
+/// Claims carried by an open tracking (pixel) token
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenClaims {
+    pub rid: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
+}
+
+/// expiry
+/// Returns the expiry timestamp for a new token, if TRACKING_TOKEN_TTL_DAYS is set
+fn expiry() -> Option<u64> {
+    let ttl_days = config::get_environments().tracking_token_ttl_days;
+    if ttl_days == 0 {
+        return None;
+    }
+    Some(jsonwebtoken::get_current_timestamp() + ttl_days * 24 * 60 * 60)
+}
+
+/// sign
+/// Signs the claims with HS256 using TRACKING_SECRET
+fn sign<T: Serialize>(claims: &T) -> String {
+    let envs = config::get_environments();
+    encode(
+        &Header::default(),
+        claims,
+        &EncodingKey::from_secret(envs.tracking_secret.as_bytes()),
+    )
+    .expect("Failed to encode tracking token")
+}
+
+/// verify
+/// Returns the claims if the signature is valid and the token has not expired
+fn verify<T: DeserializeOwned>(token: &str) -> Option<T> {
+    let envs = config::get_environments();
+    let mut validation = Validation::new(Algorithm::HS256);
+    // exp is optional, but validated when present
+    validation.required_spec_claims.clear();
+    validation.leeway = 0;
+    decode::<T>(
+        token,
+        &DecodingKey::from_secret(envs.tracking_secret.as_bytes()),
+        &validation,
+    )
+    .ok()
+    .map(|data| data.claims)
+}
+
+/// is_redirectable
+/// Returns true if the URL is an absolute http(s) URL that can be sent as a Location header
+fn is_redirectable(url: &str) -> bool {
+    let lower = url.to_ascii_lowercase();
+    (lower.starts_with("http://") || lower.starts_with("https://"))
+        && HeaderValue::from_str(url).is_ok()
+}
+
+/// click_token
+/// Creates a signed token for a tracked link, or None if the URL cannot be redirected to
+/// Click tokens never expire so links in old emails keep reaching their destination
+pub fn click_token(request_id: i32, url: &str) -> Option<String> {
+    if !is_redirectable(url) {
+        return None;
+    }
+    Some(sign(&ClickClaims {
+        rid: request_id,
+        url: url.to_string(),
+        exp: None,
+    }))
+}
+
+/// verify_click_token
+/// Returns the claims if the click token is valid and points to a redirectable URL
+pub fn verify_click_token(token: &str) -> Option<ClickClaims> {
+    verify::<ClickClaims>(token).filter(|claims| is_redirectable(&claims.url))
+}
+
+/// open_token
+/// Creates a signed token for the open tracking pixel
+pub fn open_token(request_id: i32) -> String {
+    sign(&OpenClaims {
+        rid: request_id,
+        exp: expiry(),
+    })
+}
+
+/// verify_open_token
+/// Returns the claims if the open token is valid
+pub fn verify_open_token(token: &str) -> Option<OpenClaims> {
+    verify(token)
+}
+
+/// open_pixel
+/// Returns the open tracking pixel appended to the content
+pub fn open_pixel(request_id: i32, server_url: &str) -> String {
+    format!(
+        "<img src=\"{}/v1/events/open?token={}\">",
+        server_url,
+        open_token(request_id)
+    )
+}
+
+/// rewrite_links
+/// Rewrites http(s) links in HTML content to the click tracking endpoint
+/// Links that cannot be redirected to are left as they are
+pub fn rewrite_links(content: &str, request_id: i32, server_url: &str) -> String {
+    HREF_REGEX
+        .replace_all(content, |caps: &Captures| {
+            let url = caps
+                .get(1)
+                .or_else(|| caps.get(2))
+                .map(|m| m.as_str())
+                .unwrap_or_default()
+                .replace("&amp;", "&");
+            match click_token(request_id, &url) {
+                Some(token) => format!("href=\"{}/v1/events/click?token={}\"", server_url, token),
+                None => caps[0].to_string(),
+            }
+        })
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_links() {
+        crate::tests::set_test_environments();
+        let content = r#"<a href="https://example.com/a?x=1&amp;y=2">A</a> <a href='http://example.com/b'>B</a> <a href="mailto:me@example.com">M</a>"#;
+        let rewritten = rewrite_links(content, 7, "https://mail.example.com");
+
+        let tokens: Vec<&str> = rewritten
+            .split("https://mail.example.com/v1/events/click?token=")
+            .skip(1)
+            .map(|rest| rest.split('"').next().unwrap())
+            .collect();
+        assert_eq!(tokens.len(), 2);
+        let first = verify_click_token(tokens[0]).expect("Invalid token");
+        assert_eq!(first.rid, 7);
+        assert_eq!(first.url, "https://example.com/a?x=1&y=2");
+        assert_eq!(
+            verify_click_token(tokens[1]).unwrap().url,
+            "http://example.com/b"
+        );
+        assert!(rewritten.contains(r#"href="mailto:me@example.com""#));
+    }
+
+    #[test]
+    fn test_verify_click_token_rejects_tampering() {
+        crate::tests::set_test_environments();
+        let token = click_token(1, "https://example.com").unwrap();
+        let mut tampered = token.clone();
+        tampered.pop();
+        assert!(verify_click_token(&tampered).is_none());
+        assert!(verify_click_token("not-a-token").is_none());
+        assert!(verify_click_token(&token).unwrap().exp.is_none());
+    }
+
+    #[test]
+    fn test_click_token_rejects_unsafe_urls() {
+        crate::tests::set_test_environments();
+        assert!(click_token(1, "javascript:alert(1)").is_none());
+        assert!(click_token(1, "https://example.com/\nSet-Cookie: x=1").is_none());
+        assert!(click_token(1, "HTTPS://example.com").is_some());
+
+        // Tokens signed before the check was added are not redirected either
+        let token = sign(&ClickClaims {
+            rid: 1,
+            url: "javascript:alert(1)".to_string(),
+            exp: None,
+        });
+        assert!(verify_click_token(&token).is_none());
+
+        let content = "<a href=\"https://example.com/a\nb\">A</a>";
+        assert_eq!(
+            rewrite_links(content, 1, "https://mail.example.com"),
+            content
+        );
+    }
+
+    #[test]
+    fn test_open_token_roundtrip() {
+        crate::tests::set_test_environments();
+        let token = open_token(42);
+        assert_eq!(verify_open_token(&token).unwrap().rid, 42);
+        assert!(open_pixel(42, "https://mail.example.com")
+            .contains(&format!("/v1/events/open?token={}", token)));
+    }
+
+    #[test]
+    fn test_verify_rejects_expired_token() {
+        crate::tests::set_test_environments();
+        let token = sign(&OpenClaims {
+            rid: 1,
+            exp: Some(jsonwebtoken::get_current_timestamp() - 10),
+        });
+        assert!(verify_open_token(&token).is_none());
+    }
+}
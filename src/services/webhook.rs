@@ -0,0 +1,362 @@
+use crate::config;
+use crate::models::webhook::{Webhook, WebhookDelivery, WebhookEvent};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use sqlx::SqlitePool;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio_util::sync::CancellationToken;
+
+/// SIGNATURE_HEADER
+/// `sha256=<hex>` HMAC of `<timestamp>.<body>` keyed with the webhook secret
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+
+/// TIMESTAMP_HEADER
+/// Unix time the delivery was signed at; receivers should reject stale timestamps
+pub const TIMESTAMP_HEADER: &str = "x-webhook-timestamp";
+
+/// DELIVERY_HEADER
+/// Delivery ID, stable across retries so receivers can deduplicate
+pub const DELIVERY_HEADER: &str = "x-webhook-delivery";
+
+/// to_hex
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// generate_secret
+/// Random 32-byte secret used when none is given
+pub fn generate_secret() -> Result<String, openssl::error::ErrorStack> {
+    let mut bytes = [0u8; 32];
+    openssl::rand::rand_bytes(&mut bytes)?;
+    Ok(to_hex(&bytes))
+}
+
+/// sign
+/// Signature sent in the SIGNATURE_HEADER
+pub fn sign(
+    secret: &str,
+    timestamp: u64,
+    payload: &str,
+) -> Result<String, openssl::error::ErrorStack> {
+    let key = PKey::hmac(secret.as_bytes())?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(timestamp.to_string().as_bytes())?;
+    signer.update(b".")?;
+    signer.update(payload.as_bytes())?;
+    Ok(format!("sha256={}", to_hex(&signer.sign_to_vec()?)))
+}
+
+/// WebhookDispatcher
+/// Turns saved results into webhook deliveries and sends them
+/// Failed attempts are retried with exponential backoff until `max_attempts`
+pub struct WebhookDispatcher {
+    client: reqwest::Client,
+    max_attempts: i64,
+    backoff_seconds: i64,
+}
+
+impl WebhookDispatcher {
+    pub fn new(max_attempts: i64, backoff_seconds: i64, timeout: Duration) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(timeout)
+                .build()
+                .unwrap_or_default(),
+            max_attempts: max_attempts.max(1),
+            backoff_seconds,
+        }
+    }
+
+    /// from_environments
+    pub fn from_environments() -> Self {
+        let envs = config::get_environments();
+        Self::new(
+            envs.webhook_max_attempts,
+            envs.webhook_backoff_seconds,
+            Duration::from_secs(envs.webhook_timeout_seconds),
+        )
+    }
+
+    /// backoff
+    /// Delay before the attempt following `attempts` failed ones (capped at 2^10 times the base)
+    pub fn backoff(&self, attempts: i64) -> i64 {
+        let exponent = (attempts - 1).clamp(0, 10) as u32;
+        self.backoff_seconds * 2i64.pow(exponent)
+    }
+
+    /// enqueue
+    /// Creates deliveries for results saved since each webhook's cursor
+    /// Returns the number of created deliveries
+    pub async fn enqueue(&self, db_pool: &SqlitePool) -> Result<usize, sqlx::Error> {
+        let mut enqueued = 0;
+        for webhook in Webhook::list(db_pool).await? {
+            let Some(webhook_id) = webhook.id else {
+                continue;
+            };
+            let events = WebhookEvent::list_after(
+                db_pool,
+                webhook.last_result_id,
+                webhook.topic_id.as_deref(),
+                500,
+            )
+            .await?;
+            let Some(last_result_id) = events.last().map(|event| event.result_id) else {
+                continue;
+            };
+            let deliveries: Vec<(i64, String)> = events
+                .into_iter()
+                .filter(|event| webhook.matches(&event.event_type))
+                .filter_map(|event| {
+                    let payload = serde_json::to_string(&event).ok()?;
+                    Some((event.result_id, payload))
+                })
+                .collect();
+            enqueued += deliveries.len();
+            WebhookDelivery::enqueue(db_pool, webhook_id, deliveries, last_result_id).await?;
+        }
+        Ok(enqueued)
+    }
+
+    /// deliver
+    /// Sends every due delivery once
+    /// Returns the number of successful deliveries
+    pub async fn deliver(&self, db_pool: &SqlitePool) -> Result<usize, sqlx::Error> {
+        let mut delivered = 0;
+        for delivery in WebhookDelivery::get_due(db_pool, 100).await? {
+            let (status_code, error) = match self
+                .send(
+                    &delivery.url,
+                    &delivery.secret,
+                    delivery.id,
+                    &delivery.payload,
+                )
+                .await
+            {
+                Ok(status) if status.is_success() => {
+                    WebhookDelivery::mark_delivered(db_pool, delivery.id, status.as_u16() as i64)
+                        .await?;
+                    delivered += 1;
+                    continue;
+                }
+                Ok(status) => (Some(status.as_u16() as i64), format!("HTTP {}", status)),
+                Err(e) => (None, e),
+            };
+            let attempts = delivery.attempts + 1;
+            let retry_in = (attempts < self.max_attempts).then(|| self.backoff(attempts));
+            if retry_in.is_none() {
+                eprintln!(
+                    "Webhook delivery {} failed after {} attempts: {}",
+                    delivery.id, attempts, error
+                );
+            }
+            WebhookDelivery::mark_attempt_failed(
+                db_pool,
+                delivery.id,
+                status_code,
+                &error,
+                retry_in,
+            )
+            .await?;
+        }
+        Ok(delivered)
+    }
+
+    /// send
+    /// Posts the signed payload
+    async fn send(
+        &self,
+        url: &str,
+        secret: &str,
+        delivery_id: i64,
+        payload: &str,
+    ) -> Result<reqwest::StatusCode, String> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let signature = sign(secret, timestamp, payload).map_err(|e| e.to_string())?;
+        let response = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, signature)
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(DELIVERY_HEADER, delivery_id.to_string())
+            .body(payload.to_string())
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(response.status())
+    }
+}
+
+/// dispatch_webhooks
+/// Enqueues and sends webhook deliveries every few seconds until shutdown
+pub async fn dispatch_webhooks(
+    dispatcher: WebhookDispatcher,
+    db_pool: SqlitePool,
+    shutdown: CancellationToken,
+) {
+    while !shutdown.is_cancelled() {
+        if let Err(e) = dispatcher.enqueue(&db_pool).await {
+            eprintln!("Failed to enqueue webhook deliveries: {:?}", e);
+        }
+        if let Err(e) = dispatcher.deliver(&db_pool).await {
+            eprintln!("Failed to deliver webhooks: {:?}", e);
+        }
+        tokio::select! {
+            _ = shutdown.cancelled() => {}
+            _ = tokio::time::sleep(Duration::from_secs(5)) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::webhook::DELIVERY_STATUS_FAILED;
+    use axum::body::Bytes;
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    /// Receiver
+    /// Local HTTP endpoint that records requests
+    /// `/flaky` fails the first request, `/down` always fails
+    #[derive(Clone, Default)]
+    struct Receiver {
+        requests: Arc<Mutex<Vec<(HeaderMap, String)>>>,
+        flaky_calls: Arc<AtomicUsize>,
+    }
+
+    async fn flaky(
+        State(receiver): State<Receiver>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> StatusCode {
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        receiver.requests.lock().unwrap().push((headers, body));
+        if receiver.flaky_calls.fetch_add(1, Ordering::SeqCst) == 0 {
+            StatusCode::INTERNAL_SERVER_ERROR
+        } else {
+            StatusCode::OK
+        }
+    }
+
+    async fn down() -> StatusCode {
+        StatusCode::SERVICE_UNAVAILABLE
+    }
+
+    async fn serve(receiver: Receiver) -> String {
+        let app = axum::Router::new()
+            .route("/flaky", axum::routing::post(flaky))
+            .route("/down", axum::routing::post(down))
+            .with_state(receiver);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    async fn register(db_pool: &SqlitePool, url: String, event_types: Vec<String>) -> Webhook {
+        Webhook {
+            id: None,
+            url,
+            secret: "secret".to_string(),
+            topic_id: Some("topic_id".to_string()),
+            event_types,
+            last_result_id: 0,
+            created_at: None,
+        }
+        .save(db_pool)
+        .await
+        .unwrap()
+    }
+
+    #[test]
+    fn test_backoff_doubles() {
+        let dispatcher = WebhookDispatcher::new(5, 30, Duration::from_secs(1));
+        assert_eq!(dispatcher.backoff(1), 30);
+        assert_eq!(dispatcher.backoff(2), 60);
+        assert_eq!(dispatcher.backoff(4), 240);
+    }
+
+    #[tokio::test]
+    async fn test_dispatcher_delivers_signed_events_with_retries() {
+        let db_pool = crate::tests::db_pool().await;
+        let receiver = Receiver::default();
+        let base_url = serve(receiver.clone()).await;
+
+        sqlx::query(
+            r#"
+            INSERT INTO email_requests (id, topic_id, email, subject, content, scheduled_at, message_id)
+            VALUES (1, 'topic_id', 'test@example.com', 'test', 'test', datetime('now'), 'ses-1'),
+                   (2, 'other', 'other@example.com', 'test', 'test', datetime('now'), 'ses-2');
+            INSERT INTO email_results (request_id, status) VALUES (1, 'Delivery');
+            "#,
+        )
+        .execute(&db_pool)
+        .await
+        .unwrap();
+        // Results saved before registration are not delivered
+        register(
+            &db_pool,
+            format!("{}/flaky", base_url),
+            vec!["Bounce".to_string()],
+        )
+        .await;
+        register(&db_pool, format!("{}/down", base_url), vec![]).await;
+        sqlx::query(
+            r#"
+            INSERT INTO email_results (request_id, status, bounce_type) VALUES (1, 'Bounce', 'Permanent');
+            INSERT INTO email_results (request_id, status) VALUES (1, 'Open');
+            INSERT INTO email_results (request_id, status) VALUES (2, 'Bounce');
+            "#,
+        )
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        let dispatcher = WebhookDispatcher::new(2, 0, Duration::from_secs(5));
+        // Bounce for /flaky, Bounce and Open for /down; the other topic is filtered out
+        assert_eq!(dispatcher.enqueue(&db_pool).await.unwrap(), 3);
+        assert_eq!(dispatcher.enqueue(&db_pool).await.unwrap(), 0);
+
+        // First attempt: /flaky fails once, /down fails
+        assert_eq!(dispatcher.deliver(&db_pool).await.unwrap(), 0);
+        // Second attempt: /flaky succeeds, /down runs out of attempts
+        assert_eq!(dispatcher.deliver(&db_pool).await.unwrap(), 1);
+        assert_eq!(dispatcher.deliver(&db_pool).await.unwrap(), 0);
+
+        let requests = receiver.requests.lock().unwrap().clone();
+        assert_eq!(requests.len(), 2);
+        let (headers, body) = &requests[1];
+        let timestamp: u64 = headers[TIMESTAMP_HEADER].to_str().unwrap().parse().unwrap();
+        assert_eq!(
+            headers[SIGNATURE_HEADER].to_str().unwrap(),
+            sign("secret", timestamp, body).unwrap()
+        );
+        assert_eq!(headers[DELIVERY_HEADER], requests[0].0[DELIVERY_HEADER]);
+        let event: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(event["event_type"], "Bounce");
+        assert_eq!(event["bounce_type"], "Permanent");
+        assert_eq!(event["email"], "test@example.com");
+        assert_eq!(event["message_id"], "ses-1");
+
+        let failed = WebhookDelivery::list(&db_pool, DELIVERY_STATUS_FAILED, None, 100)
+            .await
+            .unwrap();
+        assert_eq!(failed.len(), 2);
+        assert_eq!(failed[0].attempts, 2);
+        assert_eq!(failed[0].last_status_code, Some(503));
+        assert!(WebhookDelivery::retry(&db_pool, failed[0].id)
+            .await
+            .unwrap());
+        assert_eq!(
+            WebhookDelivery::get_due(&db_pool, 100).await.unwrap().len(),
+            1
+        );
+    }
+}
@@ -1,6 +1,10 @@
 use crate::models::request::EmailRequest;
+use crate::services::monitor::SendingPause;
+use crate::services::sns::{HttpFetcher, ReqwestFetcher, SnsVerifier};
 use sqlx::SqlitePool;
+use std::sync::Arc;
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 
 /// AppState
 /// Application state
@@ -8,6 +12,10 @@ use tokio::sync::mpsc;
 pub struct AppState {
     pub db_pool: SqlitePool,
     pub tx: mpsc::Sender<EmailRequest>,
+    pub shutdown: CancellationToken,
+    pub pause: SendingPause,
+    pub sns_verifier: Option<Arc<SnsVerifier>>,
+    pub http_fetcher: Arc<dyn HttpFetcher>,
 }
 
 impl AppState {
@@ -17,6 +25,38 @@ impl AppState {
         Self {
             db_pool,
             tx: tx.clone(),
+            shutdown: CancellationToken::new(),
+            pause: SendingPause::default(),
+            sns_verifier: None,
+            http_fetcher: Arc::new(ReqwestFetcher::default()),
         }
     }
+
+    /// with_shutdown
+    /// Uses the given token to signal that the server is shutting down
+    pub fn with_shutdown(mut self, shutdown: CancellationToken) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// with_pause
+    /// Shares the sending pause switch with the sender and the rate monitor
+    pub fn with_pause(mut self, pause: SendingPause) -> Self {
+        self.pause = pause;
+        self
+    }
+
+    /// with_sns_verifier
+    /// Requires SNS events to carry a valid signature
+    pub fn with_sns_verifier(mut self, verifier: Arc<SnsVerifier>) -> Self {
+        self.sns_verifier = Some(verifier);
+        self
+    }
+
+    /// with_http_fetcher
+    /// Uses the given client for outgoing requests such as SNS subscription confirmations
+    pub fn with_http_fetcher(mut self, fetcher: Arc<dyn HttpFetcher>) -> Self {
+        self.http_fetcher = fetcher;
+        self
+    }
 }
@@ -1,78 +1,9 @@
 #[cfg(test)]
 mod tests {
-    use jsonwebtoken::{encode, EncodingKey, Header};
-    use serde::{Deserialize, Serialize};
+    use crate::tests::{authorize, db_pool};
     use sqlx::Row;
-    use std::env;
     use tower::util::ServiceExt;
 
-    async fn db_pool() -> sqlx::sqlite::SqlitePool {
-        let db_pool = sqlx::sqlite::SqlitePoolOptions::new()
-            .max_connections(10)
-            .connect("sqlite::memory:")
-            .await
-            .expect("Failed to create pool");
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS email_requests (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                topic_id VARCHAR(255) NOT NULL,
-                message_id VARCHAR(255) DEFAULT NULL,
-                email VARCHAR(255) NOT NULL,
-                subject VARCHAR(255) NOT NULL,
-                content TEXT NOT NULL,
-                scheduled_at DATETIME NOT NULL,
-                status TINYINT NOT NULL DEFAULT 0,
-                error VARCHAR(255) DEFAULT NULL,
-                created_at DATETIME NOT NULL DEFAULT (datetime('now')),
-                updated_at DATETIME NOT NULL DEFAULT (datetime('now')),
-                deleted_at DATETIME
-            );
-
-            CREATE INDEX idx_requests_status ON email_requests(status);
-            CREATE INDEX idx_requests_scheduled_at ON email_requests(scheduled_at DESC);
-            CREATE INDEX idx_requests_topic_id ON email_requests(topic_id);
-
-            CREATE TABLE IF NOT EXISTS email_results (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                request_id INTEGER NOT NULL,
-                status VARCHAR(50) NOT NULL,
-                raw TEXT,
-                created_at DATETIME NOT NULL DEFAULT (datetime('now')),
-                FOREIGN KEY (request_id) REFERENCES email_requests(id)
-            );
-
-            CREATE INDEX idx_results_status ON email_results(status);
-            "#,
-        )
-        .execute(&db_pool)
-        .await
-        .expect("Failed to create tables");
-        db_pool
-    }
-
-    async fn authorize() -> String {
-        #[derive(Debug, Serialize, Deserialize)]
-        struct Claims {
-            sub: String,
-            exp: usize,
-        }
-
-        let jwt_secret = "secret";
-        env::set_var("JWT_SECRET", jwt_secret);
-        let claims = Claims {
-            sub: "".to_string(),
-            exp: 10000000000,
-        };
-        let token = encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(jwt_secret.as_ref()),
-        )
-        .expect("Failed to generate JWT token");
-        token
-    }
-
     #[tokio::test]
     async fn test_open_message_handler_success_get_image() {
         // Test to return a 1x1 blank image to create an email open event
@@ -117,8 +48,10 @@ mod tests {
         let app = crate::app::app(crate::state::AppState::new(db_pool.clone(), cloned_tx_send))
             .await
             .unwrap();
+        crate::tests::set_test_environments();
+        let token = crate::services::tracking::open_token(1);
         let response = axum::http::Request::builder()
-            .uri("/v1/events/open?request_id=1")
+            .uri(format!("/v1/events/open?token={}", token))
             .method("GET")
             .body(axum::body::Body::empty())
             .unwrap();
@@ -133,6 +66,41 @@ mod tests {
         assert_eq!(result.get::<String, _>("status"), "Open");
     }
 
+    #[tokio::test]
+    async fn test_open_message_handler_ignores_forged_request() {
+        // Test that unsigned or tampered open requests are not recorded
+        // 1. Request the pixel with a raw request_id and with a tampered token
+        // 2. Check if the image is still returned
+        // 3. Check if no open event is saved
+        crate::tests::set_test_environments();
+        let db_pool = db_pool().await;
+        let (tx_send, _) = tokio::sync::mpsc::channel(1);
+        let app = crate::app::app(crate::state::AppState::new(db_pool.clone(), tx_send))
+            .await
+            .unwrap();
+        let mut tampered = crate::services::tracking::open_token(1);
+        tampered.pop();
+        for uri in [
+            "/v1/events/open?request_id=1".to_string(),
+            format!("/v1/events/open?token={}", tampered),
+        ] {
+            let request = axum::http::Request::builder()
+                .uri(uri)
+                .method("GET")
+                .body(axum::body::Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), axum::http::StatusCode::OK);
+            assert_eq!(response.headers().get("Content-Type").unwrap(), "image/png");
+        }
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM email_results")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
     #[tokio::test]
     async fn test_open_message_handler_fail_path_in_slash() {
         // Test to return a 1x1 blank image to create an email open event
@@ -256,4 +224,356 @@ mod tests {
             serde_json::from_slice(&body).unwrap();
         assert_eq!(body.count, 0);
     }
+
+    #[tokio::test]
+    async fn test_click_message_handler_success_redirect() {
+        // Test to record a click event and redirect to the original URL
+        // 1. Create an email request
+        // 2. Request the click endpoint with a signed token
+        // 3. Check if a 302 status is returned with the original URL as Location
+        // 4. Check if a Click result with the URL is saved, and counted in the topic report
+        let db_pool = db_pool().await;
+        sqlx::query(
+            r#"
+            INSERT INTO email_requests (id, topic_id, email, subject, content, scheduled_at)
+            VALUES (1, 'topic_id', 'test', 'test', 'test', datetime('now'));
+            "#,
+        )
+        .execute(&db_pool)
+        .await
+        .expect("Failed to insert email request");
+
+        crate::tests::set_test_environments();
+        let token =
+            crate::services::tracking::click_token(1, "https://example.com/landing").unwrap();
+        for _ in 0..2 {
+            let (tx_send, _) = tokio::sync::mpsc::channel(1);
+            let app = crate::app::app(crate::state::AppState::new(db_pool.clone(), tx_send))
+                .await
+                .unwrap();
+            let request = axum::http::Request::builder()
+                .uri(format!("/v1/events/click?token={}", token))
+                .method("GET")
+                .body(axum::body::Body::empty())
+                .unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), axum::http::StatusCode::FOUND);
+            assert_eq!(
+                response.headers().get("Location").unwrap(),
+                "https://example.com/landing"
+            );
+        }
+
+        let result = sqlx::query("SELECT * FROM email_results WHERE request_id = 1")
+            .fetch_one(&db_pool)
+            .await
+            .expect("Failed to fetch email result");
+        assert_eq!(result.get::<String, _>("status"), "Click");
+        assert_eq!(
+            result.get::<String, _>("raw"),
+            "https://example.com/landing"
+        );
+
+        let (status, body) = crate::tests::call(db_pool, "GET", "/v1/topics/topic_id", None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(body["click_counts"]["total"], 2);
+        assert_eq!(body["click_counts"]["unique"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_click_message_handler_fail_invalid_token() {
+        // Test to reject a click with a forged token without redirecting
+        let db_pool = db_pool().await;
+        let (tx_send, _) = tokio::sync::mpsc::channel(1);
+        let app = crate::app::app(crate::state::AppState::new(db_pool.clone(), tx_send))
+            .await
+            .unwrap();
+        let request = axum::http::Request::builder()
+            .uri("/v1/events/click?token=forged")
+            .method("GET")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::BAD_REQUEST);
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM email_results")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_create_event_handler_verifies_signature() {
+        // Test that SNS events are only saved with a valid signature
+        // 1. An unsigned and a tampered message are rejected with 403
+        // 2. A signed message is saved
+        let db_pool = db_pool().await;
+        sqlx::query(
+            r#"
+            INSERT INTO email_requests (id, topic_id, email, subject, content, scheduled_at, message_id)
+            VALUES (1, 'topic_id', 'test@example.com', 'test', 'test', datetime('now'), 'ses-1');
+            "#,
+        )
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        let signer = crate::services::sns::tests::TestSigner::new();
+        let (verifier, _) = signer.verifier();
+        let (tx_send, _) = tokio::sync::mpsc::channel(1);
+        let app = crate::app::app(
+            crate::state::AppState::new(db_pool.clone(), tx_send)
+                .with_sns_verifier(std::sync::Arc::new(verifier)),
+        )
+        .await
+        .unwrap();
+
+        let unsigned = serde_json::json!({
+            "Type": "Notification",
+            "MessageId": "sns-1",
+            "TopicArn": "arn:aws:sns:ap-northeast-2:123456789012:ses-events",
+            "Message": serde_json::json!({
+                "notificationType": "Delivery",
+                "mail": {"messageId": "ses-1"}
+            })
+            .to_string(),
+            "Timestamp": "2025-01-01T00:00:00.000Z",
+        });
+        let signed = signer.sign(unsigned.clone(), "2");
+        let mut tampered = signed.clone();
+        tampered["MessageId"] = "sns-2".into();
+
+        for (body, expected) in [
+            (unsigned, axum::http::StatusCode::FORBIDDEN),
+            (tampered, axum::http::StatusCode::FORBIDDEN),
+            (signed, axum::http::StatusCode::OK),
+        ] {
+            let request = axum::http::Request::builder()
+                .uri("/v1/events/results")
+                .method("POST")
+                .header("x-amz-sns-message-type", "Notification")
+                .body(axum::body::Body::from(body.to_string()))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), expected);
+        }
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM email_results")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_create_event_handler_stores_structured_fields() {
+        // Test that event-publishing (eventType) records are parsed and their key fields stored
+        // 1. A published Bounce event is saved with its type, sub type and diagnostic code
+        // 2. The topic reports bounce counts by type/sub type
+        let db_pool = db_pool().await;
+        sqlx::query(
+            r#"
+            INSERT INTO email_requests (id, topic_id, email, subject, content, scheduled_at, message_id)
+            VALUES (1, 'topic_id', 'gone@example.com', 'test', 'test', datetime('now'), 'ses-1');
+            "#,
+        )
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        let (tx_send, _) = tokio::sync::mpsc::channel(1);
+        let app = crate::app::app(crate::state::AppState::new(db_pool.clone(), tx_send))
+            .await
+            .unwrap();
+        let message = serde_json::json!({
+            "eventType": "Bounce",
+            "mail": {"messageId": "ses-1", "timestamp": "2025-01-01T00:00:00.000Z"},
+            "bounce": {
+                "bounceType": "Permanent",
+                "bounceSubType": "NoEmail",
+                "timestamp": "2025-01-01T00:00:01.000Z",
+                "bouncedRecipients": [{
+                    "emailAddress": "gone@example.com",
+                    "diagnosticCode": "smtp; 550 5.1.1 user unknown"
+                }]
+            }
+        });
+        let body = serde_json::json!({
+            "Type": "Notification",
+            "MessageId": "sns-1",
+            "TopicArn": "arn:aws:sns:ap-northeast-2:123456789012:ses-events",
+            "Message": message.to_string(),
+        });
+        let request = axum::http::Request::builder()
+            .uri("/v1/events/results")
+            .method("POST")
+            .header("x-amz-sns-message-type", "Notification")
+            .body(axum::body::Body::from(body.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let result = sqlx::query("SELECT * FROM email_results WHERE request_id = 1")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(result.get::<String, _>("status"), "Bounce");
+        assert_eq!(result.get::<String, _>("bounce_type"), "Permanent");
+        assert_eq!(result.get::<String, _>("bounce_sub_type"), "NoEmail");
+        assert_eq!(
+            result.get::<String, _>("diagnostic_code"),
+            "smtp; 550 5.1.1 user unknown"
+        );
+        assert_eq!(
+            result.get::<String, _>("event_at"),
+            "2025-01-01T00:00:01.000Z"
+        );
+
+        let (status, body) = crate::tests::call(db_pool, "GET", "/v1/topics/topic_id", None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(body["bounce_counts"]["Permanent/NoEmail"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_create_event_handler_skips_redelivered_notification() {
+        // Test that a notification redelivered by SNS is stored only once
+        // 1. Both deliveries are acknowledged with 200
+        // 2. Only one result is saved
+        // 3. Expired dedup keys are pruned
+        let db_pool = db_pool().await;
+        sqlx::query(
+            r#"
+            INSERT INTO email_requests (id, topic_id, email, subject, content, scheduled_at, message_id)
+            VALUES (1, 'topic_id', 'test@example.com', 'test', 'test', datetime('now'), 'ses-1');
+            "#,
+        )
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        let (tx_send, _) = tokio::sync::mpsc::channel(1);
+        let app = crate::app::app(crate::state::AppState::new(db_pool.clone(), tx_send))
+            .await
+            .unwrap();
+        let message = serde_json::json!({
+            "notificationType": "Delivery",
+            "mail": {"messageId": "ses-1"},
+            "delivery": {"recipients": ["test@example.com"]}
+        });
+        let body = serde_json::json!({
+            "Type": "Notification",
+            "MessageId": "sns-1",
+            "Message": message.to_string(),
+        });
+        for _ in 0..2 {
+            let request = axum::http::Request::builder()
+                .uri("/v1/events/results")
+                .method("POST")
+                .header("x-amz-sns-message-type", "Notification")
+                .body(axum::body::Body::from(body.to_string()))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), axum::http::StatusCode::OK);
+        }
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM email_results")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+
+        sqlx::query("UPDATE processed_events SET created_at = datetime('now', '-15 days')")
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        let pruned = crate::models::processed_event::ProcessedEvent::prune(&db_pool, 14)
+            .await
+            .unwrap();
+        assert_eq!(pruned, 1);
+    }
+
+    #[tokio::test]
+    async fn test_create_event_handler_stores_orphan_event() {
+        // Test that an event for an unknown message ID is kept and reconciled later
+        // 1. The event is acknowledged with 200 and listed as an orphan
+        // 2. Once the message ID is written, the reconciler saves the result and removes the orphan
+        let db_pool = db_pool().await;
+        let (tx_send, _) = tokio::sync::mpsc::channel(1);
+        let app = crate::app::app(crate::state::AppState::new(db_pool.clone(), tx_send))
+            .await
+            .unwrap();
+        let message = serde_json::json!({
+            "notificationType": "Delivery",
+            "mail": {"messageId": "ses-late"},
+            "delivery": {"recipients": ["test@example.com"]}
+        });
+        let body = serde_json::json!({
+            "Type": "Notification",
+            "MessageId": "sns-1",
+            "Message": message.to_string(),
+        });
+        let request = axum::http::Request::builder()
+            .uri("/v1/events/results")
+            .method("POST")
+            .header("x-amz-sns-message-type", "Notification")
+            .body(axum::body::Body::from(body.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::OK);
+
+        let (status, body) =
+            crate::tests::call(db_pool.clone(), "GET", "/v1/events/orphans", None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(body["count"], 1);
+        assert_eq!(body["events"][0]["ses_message_id"], "ses-late");
+        assert_eq!(body["events"][0]["event_type"], "Delivery");
+
+        // Nothing to attach yet
+        let reconciled = crate::services::events::reconcile_orphan_events(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(reconciled, 0);
+
+        sqlx::query(
+            r#"
+            INSERT INTO email_requests (id, topic_id, email, subject, content, scheduled_at, message_id)
+            VALUES (1, 'topic_id', 'test@example.com', 'test', 'test', datetime('now'), 'ses-late');
+            "#,
+        )
+        .execute(&db_pool)
+        .await
+        .unwrap();
+        let reconciled = crate::services::events::reconcile_orphan_events(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(reconciled, 1);
+
+        let result = sqlx::query("SELECT * FROM email_results WHERE request_id = 1")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(result.get::<String, _>("status"), "Delivery");
+        let (_, body) =
+            crate::tests::call(db_pool.clone(), "GET", "/v1/events/orphans", None).await;
+        assert_eq!(body["count"], 0);
+
+        // Events that never match are dropped after the retention period
+        sqlx::query(
+            r#"
+            INSERT INTO orphan_events (sns_message_id, ses_message_id, event_type, raw, created_at)
+            VALUES ('sns-old', 'ses-other', 'Delivery', '{}', datetime('now', '-31 days')),
+                   ('sns-new', 'ses-other', 'Delivery', '{}', datetime('now'));
+            "#,
+        )
+        .execute(&db_pool)
+        .await
+        .unwrap();
+        let pruned = crate::models::orphan_event::OrphanEvent::prune(&db_pool, 30)
+            .await
+            .unwrap();
+        assert_eq!(pruned, 1);
+        let (_, body) = crate::tests::call(db_pool, "GET", "/v1/events/orphans", None).await;
+        assert_eq!(body["count"], 1);
+    }
 }
@@ -0,0 +1,632 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::request::{EmailMessageStatus, EmailRequest};
+    use crate::tests::{call, db_pool};
+    use sqlx::Row;
+
+    #[tokio::test]
+    async fn test_create_message_handler_renders_recipient_vars() {
+        // Subject and content are rendered per recipient
+        // 1. A recipient with all variables gets the rendered subject/content
+        // 2. A recipient missing a variable is saved as Failed with the rendering error
+        let db_pool = db_pool().await;
+        let (status, _) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/messages",
+            Some(serde_json::json!({
+                "messages": [{
+                    "topic_id": "newsletter",
+                    "recipients": [
+                        {"email": "jane@example.com", "vars": {"first_name": "Jane"}},
+                        {"email": "john@example.com", "vars": {}}
+                    ],
+                    "subject": "Hi {{first_name}}",
+                    "content": "<p>Hello {{first_name}}</p>"
+                }],
+                "scheduled_at": "2099-01-01 00:00:00"
+            })),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        let jane = sqlx::query("SELECT * FROM email_requests WHERE email = 'jane@example.com'")
+            .fetch_one(&db_pool)
+            .await
+            .expect("Failed to fetch jane's request");
+        assert_eq!(jane.get::<String, _>("subject"), "Hi Jane");
+        assert_eq!(jane.get::<String, _>("content"), "<p>Hello Jane</p>");
+        assert_eq!(
+            jane.get::<i64, _>("status"),
+            EmailMessageStatus::Created as i64
+        );
+
+        let john = sqlx::query("SELECT * FROM email_requests WHERE email = 'john@example.com'")
+            .fetch_one(&db_pool)
+            .await
+            .expect("Failed to fetch john's request");
+        assert_eq!(
+            john.get::<i64, _>("status"),
+            EmailMessageStatus::Failed as i64
+        );
+        assert!(john.get::<Option<String>, _>("error").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_create_message_handler_rejects_oversized_render() {
+        // The length limits apply to the rendered message of each recipient
+        let db_pool = db_pool().await;
+        let long_name = "a".repeat(crate::validation::MAX_SUBJECT_LENGTH);
+        let (status, body) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/messages",
+            Some(serde_json::json!({
+                "messages": [{
+                    "topic_id": "newsletter",
+                    "recipients": [
+                        {"email": "jane@example.com", "vars": {"first_name": "Jane"}},
+                        {"email": "long@example.com", "vars": {"first_name": long_name}}
+                    ],
+                    "subject": "Hi {{first_name}}",
+                    "content": "<p>Hello</p>"
+                }],
+                "scheduled_at": "2099-01-01 00:00:00"
+            })),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(body["scheduled"], 1);
+        assert_eq!(body["rejected"], 1);
+        assert_eq!(body["results"][1]["status"], "rejected");
+        assert_eq!(
+            body["results"][1]["error"],
+            format!(
+                "Rendered subject must be at most {} bytes",
+                crate::validation::MAX_SUBJECT_LENGTH
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_message_handler_keeps_plain_emails_unrendered() {
+        // Messages using `emails` are sent as-is, even if they contain template syntax
+        let db_pool = db_pool().await;
+        let (status, _) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/messages",
+            Some(serde_json::json!({
+                "messages": [{
+                    "topic_id": "plain",
+                    "emails": ["plain@example.com"],
+                    "subject": "Hi {{first_name}}",
+                    "content": "content"
+                }],
+                "scheduled_at": "2099-01-01 00:00:00"
+            })),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        let subject: String = sqlx::query_scalar("SELECT subject FROM email_requests")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(subject, "Hi {{first_name}}");
+    }
+
+    #[tokio::test]
+    async fn test_create_message_handler_uses_stored_template() {
+        // Messages referencing a template use its latest version and record it on each request
+        let db_pool = db_pool().await;
+        let (status, template) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/templates",
+            Some(serde_json::json!({
+                "name": "welcome",
+                "subject": "Welcome v1",
+                "content": "<p>v1</p>"
+            })),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+        let template_id = template["id"].as_i64().unwrap();
+        let (status, _) = call(
+            db_pool.clone(),
+            "PUT",
+            &format!("/v1/templates/{}", template_id),
+            Some(serde_json::json!({
+                "subject": "Welcome {{first_name}}",
+                "content": "<p>Hi {{first_name}}</p>"
+            })),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        let (status, _) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/messages",
+            Some(serde_json::json!({
+                "messages": [{
+                    "topic_id": "welcome",
+                    "recipients": [{"email": "jane@example.com", "vars": {"first_name": "Jane"}}],
+                    "template_id": template_id
+                }],
+                "scheduled_at": "2099-01-01 00:00:00"
+            })),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        let row = sqlx::query("SELECT * FROM email_requests")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(row.get::<String, _>("subject"), "Welcome Jane");
+        assert_eq!(row.get::<i64, _>("template_id"), template_id);
+        assert_eq!(row.get::<i64, _>("template_version"), 2);
+    }
+
+    #[tokio::test]
+    async fn test_create_message_handler_unknown_template() {
+        let db_pool = db_pool().await;
+        let (status, _) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/messages",
+            Some(serde_json::json!({
+                "messages": [{"emails": ["a@example.com"], "template_id": 42}]
+            })),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM email_requests")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_create_message_handler_returns_recipient_results() {
+        // Every recipient is reported with its request ID and outcome, in request order
+        // 1. Invalid addresses are rejected without a request and do not abort the batch
+        // 2. Scheduled and immediate requests are reported as scheduled and queued
+        let db_pool = db_pool().await;
+        let (status, body) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/messages",
+            Some(serde_json::json!({
+                "messages": [{
+                    "topic_id": "results",
+                    "emails": ["ok@example.com", "not-an-email", "next@example.com"],
+                    "subject": "subject",
+                    "content": "content"
+                }],
+                "scheduled_at": "2099-01-01 00:00:00"
+            })),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(body["scheduled"], 2);
+        assert_eq!(body["rejected"], 1);
+        let results = body["results"].as_array().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0]["email"], "ok@example.com");
+        assert_eq!(results[0]["status"], "scheduled");
+        assert_eq!(results[1]["email"], "not-an-email");
+        assert_eq!(results[1]["status"], "rejected");
+        assert_eq!(results[1]["request_id"], serde_json::Value::Null);
+        assert_eq!(results[1]["error"], "Invalid email address");
+        assert_eq!(results[2]["status"], "scheduled");
+
+        let request_id = results[0]["request_id"].as_i64().unwrap();
+        let email: String = sqlx::query_scalar("SELECT email FROM email_requests WHERE id = ?")
+            .bind(request_id)
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(email, "ok@example.com");
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM email_requests")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+
+        let (_, body) = call(
+            db_pool,
+            "POST",
+            "/v1/messages",
+            Some(serde_json::json!({
+                "messages": [{"emails": ["now@example.com"], "subject": "s", "content": "c"}]
+            })),
+        )
+        .await;
+        assert_eq!(body["queued"], 1);
+        assert_eq!(body["results"][0]["status"], "queued");
+        assert_eq!(body["results"][0]["topic_id"], "");
+    }
+
+    #[tokio::test]
+    async fn test_create_message_handler_rejects_invalid_input() {
+        // Invalid input is rejected as a whole with {code, message, field} and nothing is saved
+        let db_pool = db_pool().await;
+        let cases = [
+            (serde_json::json!({"messages": []}), "required", "messages"),
+            (
+                serde_json::json!({"messages": [{"emails": [], "subject": "s", "content": "c"}]}),
+                "required",
+                "messages[0].emails",
+            ),
+            (
+                serde_json::json!({"messages": [{"emails": ["a@example.com"], "subject": " ", "content": "c"}]}),
+                "required",
+                "messages[0].subject",
+            ),
+            (
+                serde_json::json!({"messages": [{"emails": ["a@example.com"], "subject": "s"}]}),
+                "required",
+                "messages[0].content",
+            ),
+            (
+                serde_json::json!({"messages": [{"emails": ["a@example.com"], "subject": "s".repeat(999), "content": "c"}]}),
+                "too_long",
+                "messages[0].subject",
+            ),
+            (
+                serde_json::json!({
+                    "messages": [{"emails": ["a@example.com"], "subject": "s", "content": "c"}],
+                    "scheduled_at": "2099-01-01T00:00:00"
+                }),
+                "invalid_format",
+                "scheduled_at",
+            ),
+        ];
+        for (payload, code, field) in cases {
+            let (status, body) = call(db_pool.clone(), "POST", "/v1/messages", Some(payload)).await;
+            assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+            assert_eq!(body["code"], code);
+            assert_eq!(body["field"], field);
+            assert!(body["message"].is_string());
+        }
+
+        // Malformed bodies use the same schema
+        let (status, body) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/messages",
+            Some(serde_json::json!({"messages": "not a list"})),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body["code"], "invalid_field");
+
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM email_requests")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_message_handler_returns_event_timeline() {
+        // A message is found by request ID or SES message ID, with its events in the order they happened
+        let db_pool = db_pool().await;
+        let (_, body) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/messages",
+            Some(serde_json::json!({
+                "messages": [{"topic_id": "detail", "emails": ["user@example.com"], "subject": "s", "content": "c"}],
+                "scheduled_at": "2099-01-01 00:00:00"
+            })),
+        )
+        .await;
+        let request_id = body["results"][0]["request_id"].as_i64().unwrap();
+        sqlx::query("UPDATE email_requests SET status = 2, message_id = 'ses-detail' WHERE id = ?")
+            .bind(request_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        // Saved out of order: the open is tracked after delivery but saved first
+        sqlx::query(
+            "INSERT INTO email_results (request_id, status, event_at, created_at) VALUES (?, 'Open', NULL, '2024-01-01 00:00:10')",
+        )
+        .bind(request_id)
+        .execute(&db_pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO email_results (request_id, status, event_at, created_at) VALUES (?, 'Delivery', '2024-01-01T00:00:05.000Z', '2024-01-01 00:00:20')",
+        )
+        .bind(request_id)
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        let (status, body) = call(
+            db_pool.clone(),
+            "GET",
+            &format!("/v1/messages/{}", request_id),
+            None,
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(body["id"], request_id);
+        assert_eq!(body["topic_id"], "detail");
+        assert_eq!(body["status"], "Sent");
+        assert_eq!(body["message_id"], "ses-detail");
+        let events = body["events"].as_array().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["event_type"], "Delivery");
+        assert_eq!(events[1]["event_type"], "Open");
+
+        let (status, by_message_id) =
+            call(db_pool.clone(), "GET", "/v1/messages/ses/ses-detail", None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(by_message_id, body);
+
+        let (status, body) = call(db_pool, "GET", "/v1/messages/ses/unknown", None).await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], "not_found");
+    }
+
+    #[tokio::test]
+    async fn test_list_messages_handler_filters_and_paginates() {
+        // Messages are listed newest first, filtered, and paged with next_cursor
+        let db_pool = db_pool().await;
+        let (_, body) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/messages",
+            Some(serde_json::json!({
+                "messages": [
+                    {"topic_id": "list-a", "emails": ["a1@example.com", "a2@example.com", "a3@example.com"], "subject": "s", "content": "c"},
+                    {"topic_id": "list-b", "emails": ["b1@example.com"], "subject": "s", "content": "c"}
+                ],
+                "scheduled_at": "2099-01-01 00:00:00"
+            })),
+        )
+        .await;
+        let bounced_id = body["results"][1]["request_id"].as_i64().unwrap();
+        sqlx::query("UPDATE email_requests SET status = 2 WHERE id = ?")
+            .bind(bounced_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO email_results (request_id, status) VALUES (?, 'Bounce')")
+            .bind(bounced_id)
+            .execute(&db_pool)
+            .await
+            .unwrap();
+
+        let (status, page) = call(
+            db_pool.clone(),
+            "GET",
+            "/v1/messages?topic_id=list-a&limit=2",
+            None,
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        let messages = page["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["email"], "a3@example.com");
+        assert_eq!(messages[1]["email"], "a2@example.com");
+        let cursor = page["next_cursor"].as_i64().unwrap();
+
+        let (_, page) = call(
+            db_pool.clone(),
+            "GET",
+            &format!("/v1/messages?topic_id=list-a&limit=2&cursor={}", cursor),
+            None,
+        )
+        .await;
+        assert_eq!(page["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(page["messages"][0]["email"], "a1@example.com");
+        assert_eq!(page["next_cursor"], serde_json::Value::Null);
+
+        let (_, page) = call(
+            db_pool.clone(),
+            "GET",
+            "/v1/messages?status=Sent&has_event=Bounce",
+            None,
+        )
+        .await;
+        let messages = page["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["id"], bounced_id);
+        assert_eq!(messages[0]["status"], "Sent");
+        let (_, page) = call(
+            db_pool.clone(),
+            "GET",
+            "/v1/messages?has_event=bounced",
+            None,
+        )
+        .await;
+        assert_eq!(page["messages"][0]["id"], bounced_id);
+        let (status, body) = call(
+            db_pool.clone(),
+            "GET",
+            "/v1/messages?has_event=bounces",
+            None,
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], "invalid_value");
+        assert_eq!(body["field"], "has_event");
+
+        let (_, page) = call(
+            db_pool.clone(),
+            "GET",
+            "/v1/messages?email=b1@example.com&scheduled_from=2098-12-31&scheduled_to=2099-01-02",
+            None,
+        )
+        .await;
+        assert_eq!(page["messages"].as_array().unwrap().len(), 1);
+        let (_, page) = call(
+            db_pool.clone(),
+            "GET",
+            "/v1/messages?created_to=2000-01-01",
+            None,
+        )
+        .await;
+        assert_eq!(page["messages"].as_array().unwrap().len(), 0);
+
+        let (status, body) = call(db_pool, "GET", "/v1/messages?status=Bounced", None).await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+        assert_eq!(body["field"], "status");
+    }
+
+    #[tokio::test]
+    async fn test_reschedule_and_cancel_message_handlers() {
+        // 1. A waiting message can be rescheduled; the change is recorded with the caller
+        // 2. Cancelling stops the message; a stopped message can neither be rescheduled nor cancelled
+        // 3. Unknown messages are 404
+        let db_pool = db_pool().await;
+        let (_, body) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/messages",
+            Some(serde_json::json!({
+                "messages": [{"topic_id": "t1", "emails": ["a@example.com"], "subject": "s", "content": "c"}],
+                "scheduled_at": "2000-01-01 00:00:00"
+            })),
+        )
+        .await;
+        let request_id = body["results"][0]["request_id"].as_i64().unwrap();
+        let uri = format!("/v1/messages/{}", request_id);
+
+        let (status, body) = call(
+            db_pool.clone(),
+            "PATCH",
+            &uri,
+            Some(serde_json::json!({"scheduled_at": "2099-01-01 00:00:00"})),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(body["status"], "Created");
+        let scheduled_at = body["scheduled_at"].as_str().unwrap().to_string();
+        assert_ne!(scheduled_at, "2000-01-01 00:00:00");
+        let (status, body) = call(
+            db_pool.clone(),
+            "PATCH",
+            &uri,
+            Some(serde_json::json!({"scheduled_at": "tomorrow"})),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+        assert_eq!(body["field"], "scheduled_at");
+
+        let (status, body) = call(db_pool.clone(), "DELETE", &uri, None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(body["status"], "Stopped");
+        assert_eq!(body["error"], "Cancelled");
+        let (status, _) = call(db_pool.clone(), "DELETE", &uri, None).await;
+        assert_eq!(status, axum::http::StatusCode::CONFLICT);
+        let (status, _) = call(
+            db_pool.clone(),
+            "PATCH",
+            &uri,
+            Some(serde_json::json!({"scheduled_at": "2099-01-01 00:00:00"})),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::CONFLICT);
+        let (status, _) = call(db_pool.clone(), "DELETE", "/v1/messages/999999", None).await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+
+        // A message claimed for sending may be in flight and is left alone
+        let (_, body) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/messages",
+            Some(serde_json::json!({
+                "messages": [{"topic_id": "t1", "emails": ["b@example.com"], "subject": "s", "content": "c"}],
+                "scheduled_at": "2000-01-01 00:00:00"
+            })),
+        )
+        .await;
+        let claimed_id = body["results"][0]["request_id"].as_i64().unwrap();
+        EmailRequest::claim_due(&db_pool, 10, 60).await.unwrap();
+        let claimed_uri = format!("/v1/messages/{}", claimed_id);
+        let (status, _) = call(db_pool.clone(), "DELETE", &claimed_uri, None).await;
+        assert_eq!(status, axum::http::StatusCode::CONFLICT);
+        let (_, body) = call(db_pool.clone(), "GET", &claimed_uri, None).await;
+        assert_eq!(body["status"], "Processed");
+
+        let (status, body) = call(
+            db_pool,
+            "GET",
+            &format!(
+                "/v1/audit-logs?target_type=message&target_id={}",
+                request_id
+            ),
+            None,
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        let audit_logs = body.as_array().unwrap();
+        assert_eq!(audit_logs.len(), 2);
+        assert_eq!(audit_logs[0]["action"], "message.cancel");
+        assert_eq!(audit_logs[0]["details"]["previous_status"], "Created");
+        assert_eq!(audit_logs[1]["action"], "message.reschedule");
+        assert_eq!(audit_logs[1]["actor"], "tester");
+        assert_eq!(audit_logs[1]["details"]["scheduled_at"], scheduled_at);
+    }
+
+    #[tokio::test]
+    async fn test_create_message_handler_converts_timezone() {
+        // 1. A time with an explicit IANA time zone or offset is stored in UTC regardless of the server's time zone
+        // 2. Unknown time zones and times skipped by DST are rejected
+        let db_pool = db_pool().await;
+        for (scheduled_at, timezone) in [
+            ("2099-01-01 09:00:00", Some("Asia/Seoul")),
+            ("2099-01-01T09:00:00+09:00", None),
+        ] {
+            let (status, body) = call(
+                db_pool.clone(),
+                "POST",
+                "/v1/messages",
+                Some(serde_json::json!({
+                    "messages": [{"topic_id": "tz", "emails": ["a@example.com"], "subject": "s", "content": "c"}],
+                    "scheduled_at": scheduled_at,
+                    "timezone": timezone
+                })),
+            )
+            .await;
+            assert_eq!(status, axum::http::StatusCode::OK);
+            let request_id = body["results"][0]["request_id"].as_i64().unwrap();
+            let (_, body) = call(
+                db_pool.clone(),
+                "GET",
+                &format!("/v1/messages/{}", request_id),
+                None,
+            )
+            .await;
+            assert_eq!(body["scheduled_at"], "2099-01-01 00:00:00");
+        }
+
+        for (timezone, code, field) in [
+            ("Seoul", "invalid_timezone", "timezone"),
+            ("America/New_York", "nonexistent_local_time", "scheduled_at"),
+        ] {
+            let (status, body) = call(
+                db_pool.clone(),
+                "POST",
+                "/v1/messages",
+                Some(serde_json::json!({
+                    "messages": [{"topic_id": "tz", "emails": ["a@example.com"], "subject": "s", "content": "c"}],
+                    "scheduled_at": "2099-03-08 02:30:00",
+                    "timezone": timezone
+                })),
+            )
+            .await;
+            assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+            assert_eq!(body["code"], code);
+            assert_eq!(body["field"], field);
+        }
+    }
+}
@@ -1 +1,267 @@
 mod event_tests;
+mod message_tests;
+mod suppression_tests;
+mod template_tests;
+mod topic_tests;
+mod webhook_tests;
+
+/// set_test_environments
+/// Sets the environment variables shared by tests before the configuration is first loaded
+#[cfg(test)]
+pub fn set_test_environments() {
+    std::env::set_var("JWT_SECRET", "secret");
+    std::env::set_var("TRACKING_SECRET", "tracking-secret");
+}
+
+/// authorize
+/// Issues a JWT signed with the test secret
+#[cfg(test)]
+pub async fn authorize() -> String {
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Claims {
+        sub: String,
+        exp: usize,
+    }
+
+    let jwt_secret = "secret";
+    set_test_environments();
+    let claims = Claims {
+        sub: "tester".to_string(),
+        exp: 10000000000,
+    };
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(jwt_secret.as_ref()),
+    )
+    .expect("Failed to generate JWT token")
+}
+
+/// call
+/// Sends an authorized request to the app and returns the status and the JSON body (Null if not JSON)
+#[cfg(test)]
+pub async fn call(
+    db_pool: sqlx::sqlite::SqlitePool,
+    method: &str,
+    uri: &str,
+    body: Option<serde_json::Value>,
+) -> (axum::http::StatusCode, serde_json::Value) {
+    use tower::util::ServiceExt;
+
+    let token = authorize().await;
+    let (tx_send, _) = tokio::sync::mpsc::channel(1);
+    let app = crate::app::app(crate::state::AppState::new(db_pool, tx_send))
+        .await
+        .unwrap();
+    let request = axum::http::Request::builder()
+        .uri(uri)
+        .method(method)
+        .header(
+            axum::http::header::AUTHORIZATION,
+            format!("Bearer {}", token),
+        )
+        .header(axum::http::header::CONTENT_TYPE, "application/json")
+        .body(match body {
+            Some(body) => axum::body::Body::from(body.to_string()),
+            None => axum::body::Body::empty(),
+        })
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (
+        status,
+        serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null),
+    )
+}
+
+/// db_pool
+/// Creates an in-memory database with the same schema as init_database.sh
+/// A single connection is used so that every query sees the same in-memory database
+#[cfg(test)]
+pub async fn db_pool() -> sqlx::sqlite::SqlitePool {
+    let db_pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("Failed to create pool");
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS email_requests (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            topic_id VARCHAR(255) NOT NULL,
+            message_id VARCHAR(255) DEFAULT NULL,
+            email VARCHAR(255) NOT NULL,
+            subject VARCHAR(255) NOT NULL,
+            content TEXT NOT NULL,
+            scheduled_at DATETIME NOT NULL,
+            status TINYINT NOT NULL DEFAULT 0,
+            error VARCHAR(255) DEFAULT NULL,
+            leased_until DATETIME DEFAULT NULL,
+            lease_id VARCHAR(32) DEFAULT NULL,
+            template_id INTEGER DEFAULT NULL,
+            template_version INTEGER DEFAULT NULL,
+            track_clicks BOOLEAN NOT NULL DEFAULT 1,
+            created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+            updated_at DATETIME NOT NULL DEFAULT (datetime('now')),
+            deleted_at DATETIME
+        );
+
+        CREATE INDEX idx_requests_status ON email_requests(status);
+        CREATE INDEX idx_requests_scheduled_at ON email_requests(scheduled_at DESC);
+        CREATE INDEX idx_requests_topic_id ON email_requests(topic_id);
+        CREATE INDEX idx_requests_leased_until ON email_requests(leased_until);
+
+        CREATE TABLE IF NOT EXISTS email_results (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            request_id INTEGER NOT NULL,
+            status VARCHAR(50) NOT NULL,
+            raw TEXT,
+            bounce_type VARCHAR(50) DEFAULT NULL,
+            bounce_sub_type VARCHAR(50) DEFAULT NULL,
+            complaint_feedback_type VARCHAR(50) DEFAULT NULL,
+            diagnostic_code TEXT DEFAULT NULL,
+            reject_reason VARCHAR(255) DEFAULT NULL,
+            delay_type VARCHAR(50) DEFAULT NULL,
+            link TEXT DEFAULT NULL,
+            event_at VARCHAR(50) DEFAULT NULL,
+            created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (request_id) REFERENCES email_requests(id)
+        );
+
+        CREATE INDEX idx_results_status ON email_results(status);
+
+        CREATE TABLE IF NOT EXISTS templates (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name VARCHAR(255) NOT NULL,
+            created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+            updated_at DATETIME NOT NULL DEFAULT (datetime('now')),
+            deleted_at DATETIME
+        );
+
+        CREATE TABLE IF NOT EXISTS template_versions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            template_id INTEGER NOT NULL,
+            version INTEGER NOT NULL,
+            subject VARCHAR(255) NOT NULL,
+            content TEXT NOT NULL,
+            created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (template_id) REFERENCES templates(id),
+            UNIQUE (template_id, version)
+        );
+
+        CREATE TABLE IF NOT EXISTS suppressions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            email VARCHAR(255) NOT NULL UNIQUE,
+            reason VARCHAR(255) NOT NULL,
+            source VARCHAR(50) NOT NULL,
+            created_at DATETIME NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS sns_subscriptions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            topic_arn VARCHAR(255) NOT NULL UNIQUE,
+            status VARCHAR(20) NOT NULL,
+            error VARCHAR(255) DEFAULT NULL,
+            created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+            updated_at DATETIME NOT NULL DEFAULT (datetime('now')),
+            confirmed_at DATETIME DEFAULT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS processed_events (
+            message_id VARCHAR(255) PRIMARY KEY,
+            created_at DATETIME NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_processed_events_created_at ON processed_events(created_at);
+
+        CREATE TABLE IF NOT EXISTS orphan_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            sns_message_id VARCHAR(255) NOT NULL,
+            ses_message_id VARCHAR(255) NOT NULL,
+            event_type VARCHAR(50) NOT NULL,
+            raw TEXT NOT NULL,
+            created_at DATETIME NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_orphan_events_ses_message_id ON orphan_events(ses_message_id);
+
+        CREATE TABLE IF NOT EXISTS webhooks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            url TEXT NOT NULL,
+            secret VARCHAR(255) NOT NULL,
+            topic_id VARCHAR(255) DEFAULT NULL,
+            event_types VARCHAR(255) DEFAULT NULL,
+            last_result_id INTEGER NOT NULL DEFAULT 0,
+            created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+            deleted_at DATETIME
+        );
+
+        CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            webhook_id INTEGER NOT NULL,
+            result_id INTEGER NOT NULL,
+            payload TEXT NOT NULL,
+            status VARCHAR(20) NOT NULL DEFAULT 'pending',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            next_attempt_at DATETIME NOT NULL DEFAULT (datetime('now')),
+            last_status_code INTEGER DEFAULT NULL,
+            last_error TEXT DEFAULT NULL,
+            created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+            updated_at DATETIME NOT NULL DEFAULT (datetime('now')),
+            delivered_at DATETIME DEFAULT NULL,
+            FOREIGN KEY (webhook_id) REFERENCES webhooks(id),
+            UNIQUE (webhook_id, result_id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_status ON webhook_deliveries(status, next_attempt_at);
+
+        CREATE INDEX IF NOT EXISTS idx_results_request_id ON email_results(request_id);
+
+        CREATE INDEX IF NOT EXISTS idx_requests_email ON email_requests(email);
+
+        CREATE TABLE IF NOT EXISTS topics (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            topic_id VARCHAR(255) NOT NULL UNIQUE,
+            name VARCHAR(255) DEFAULT NULL,
+            description TEXT DEFAULT NULL,
+            tags TEXT DEFAULT NULL,
+            created_by VARCHAR(255) DEFAULT NULL,
+            first_sent_at DATETIME DEFAULT NULL,
+            last_sent_at DATETIME DEFAULT NULL,
+            paused_at DATETIME DEFAULT NULL,
+            rate_breached_at DATETIME DEFAULT NULL,
+            created_at DATETIME NOT NULL DEFAULT (datetime('now')),
+            updated_at DATETIME NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS audit_logs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            actor VARCHAR(255) NOT NULL,
+            action VARCHAR(50) NOT NULL,
+            target_type VARCHAR(50) NOT NULL,
+            target_id VARCHAR(255) NOT NULL,
+            details TEXT DEFAULT NULL,
+            created_at DATETIME NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_audit_logs_target ON audit_logs(target_type, target_id);
+
+        -- Sending pause set manually or by the rate guard; kept across restarts
+        CREATE TABLE IF NOT EXISTS sending_pause (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            reason TEXT NOT NULL,
+            paused_at DATETIME NOT NULL
+        );
+        "#,
+    )
+    .execute(&db_pool)
+    .await
+    .expect("Failed to create tables");
+    db_pool
+}
@@ -0,0 +1,200 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::request::EmailMessageStatus;
+    use crate::models::suppression::Suppression;
+    use crate::tests::{call, db_pool};
+    use sqlx::Row;
+    use tower::util::ServiceExt;
+
+    #[tokio::test]
+    async fn test_suppression_crud() {
+        // Create, list and delete suppressions through the API
+        let db_pool = db_pool().await;
+        let (status, created) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/suppressions",
+            Some(serde_json::json!({"email": "Jane@Example.com", "reason": "Unsubscribed"})),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+        assert_eq!(created["email"], "jane@example.com");
+        assert_eq!(created["source"], "api");
+
+        let (status, listed) = call(db_pool.clone(), "GET", "/v1/suppressions", None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(listed.as_array().unwrap().len(), 1);
+        assert_eq!(listed[0]["reason"], "Unsubscribed");
+
+        let (status, _) = call(
+            db_pool.clone(),
+            "DELETE",
+            "/v1/suppressions/jane@example.com",
+            None,
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        let (status, _) = call(
+            db_pool.clone(),
+            "DELETE",
+            "/v1/suppressions/jane@example.com",
+            None,
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_create_message_handler_skips_suppressed_recipient() {
+        // Suppressed recipients are saved with the Suppressed status and never queued
+        let db_pool = db_pool().await;
+        call(
+            db_pool.clone(),
+            "POST",
+            "/v1/suppressions",
+            Some(serde_json::json!({"email": "bounced@example.com"})),
+        )
+        .await;
+        let (status, _) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/messages",
+            Some(serde_json::json!({
+                "messages": [{
+                    "topic_id": "suppressed",
+                    "emails": ["bounced@example.com", "ok@example.com"],
+                    "subject": "subject",
+                    "content": "content"
+                }],
+                "scheduled_at": "2099-01-01 00:00:00"
+            })),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        let bounced =
+            sqlx::query("SELECT * FROM email_requests WHERE email = 'bounced@example.com'")
+                .fetch_one(&db_pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            bounced.get::<i64, _>("status"),
+            EmailMessageStatus::Suppressed as i64
+        );
+        let ok = sqlx::query("SELECT * FROM email_requests WHERE email = 'ok@example.com'")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            ok.get::<i64, _>("status"),
+            EmailMessageStatus::Created as i64
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_event_handler_suppresses_permanent_bounce() {
+        // Permanent bounces are added to the suppression list; transient ones are not
+        let db_pool = db_pool().await;
+        sqlx::query(
+            r#"
+            INSERT INTO email_requests (id, topic_id, email, subject, content, scheduled_at, message_id)
+            VALUES (1, 'topic_id', 'hard@example.com', 'test', 'test', datetime('now'), 'ses-1'),
+                   (2, 'topic_id', 'soft@example.com', 'test', 'test', datetime('now'), 'ses-2');
+            "#,
+        )
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        let (tx_send, _) = tokio::sync::mpsc::channel(1);
+        let app = crate::app::app(crate::state::AppState::new(db_pool.clone(), tx_send))
+            .await
+            .unwrap();
+        for (message_id, email, bounce_type) in [
+            ("ses-1", "hard@example.com", "Permanent"),
+            ("ses-2", "soft@example.com", "Transient"),
+        ] {
+            let message = serde_json::json!({
+                "notificationType": "Bounce",
+                "mail": {"messageId": message_id},
+                "bounce": {
+                    "bounceType": bounce_type,
+                    "bounceSubType": "General",
+                    "bouncedRecipients": [{"emailAddress": email}]
+                }
+            });
+            let body = serde_json::json!({
+                "Type": "Notification",
+                "MessageId": format!("sns-{}", message_id),
+                "Message": message.to_string(),
+            });
+            let request = axum::http::Request::builder()
+                .uri("/v1/events/results")
+                .method("POST")
+                .header("x-amz-sns-message-type", "Notification")
+                .body(axum::body::Body::from(body.to_string()))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), axum::http::StatusCode::OK);
+        }
+
+        assert!(Suppression::is_suppressed(&db_pool, "hard@example.com")
+            .await
+            .unwrap());
+        assert!(!Suppression::is_suppressed(&db_pool, "soft@example.com")
+            .await
+            .unwrap());
+        let suppressions = Suppression::list(&db_pool).await.unwrap();
+        assert_eq!(suppressions[0].reason, "Permanent bounce (General)");
+        assert_eq!(suppressions[0].source, "ses");
+    }
+
+    #[tokio::test]
+    async fn test_process_notification_retries_failed_suppression() {
+        // A bounce whose suppression cannot be saved is not marked processed, so its redelivery suppresses the address
+        let db_pool = db_pool().await;
+        sqlx::query(
+            r#"
+            INSERT INTO email_requests (id, topic_id, email, subject, content, scheduled_at, message_id)
+            VALUES (1, 'topic_id', 'hard@example.com', 'test', 'test', datetime('now'), 'ses-1');
+            CREATE TRIGGER fail_suppressions BEFORE INSERT ON suppressions
+            BEGIN SELECT RAISE(ABORT, 'unavailable'); END;
+            "#,
+        )
+        .execute(&db_pool)
+        .await
+        .unwrap();
+        let message = serde_json::json!({
+            "notificationType": "Bounce",
+            "mail": {"messageId": "ses-1"},
+            "bounce": {
+                "bounceType": "Permanent",
+                "bounceSubType": "General",
+                "bouncedRecipients": [{"emailAddress": "hard@example.com"}]
+            }
+        })
+        .to_string();
+
+        assert!(
+            crate::services::events::process_notification(&db_pool, "sns-1", message.clone())
+                .await
+                .is_err()
+        );
+        sqlx::query("DROP TRIGGER fail_suppressions")
+            .execute(&db_pool)
+            .await
+            .unwrap();
+        assert!(matches!(
+            crate::services::events::process_notification(&db_pool, "sns-1", message).await,
+            Ok(crate::services::events::EventOutcome::Saved)
+        ));
+        assert!(Suppression::is_suppressed(&db_pool, "hard@example.com")
+            .await
+            .unwrap());
+        let results: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM email_results")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(results, 1);
+    }
+}
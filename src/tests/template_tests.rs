@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod tests {
+    use crate::tests::{call, db_pool};
+    use axum::http::StatusCode;
+
+    #[tokio::test]
+    async fn test_template_crud() {
+        // Create, update (new version), retrieve by version, list, and delete a template
+        let db_pool = db_pool().await;
+        let (status, created) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/templates",
+            Some(serde_json::json!({"name": "promo", "subject": "s1", "content": "c1"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["version"], 1);
+        let uri = format!("/v1/templates/{}", created["id"]);
+
+        let (status, updated) = call(
+            db_pool.clone(),
+            "PUT",
+            &uri,
+            Some(serde_json::json!({"subject": "s2", "content": "c2"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(updated["version"], 2);
+
+        let (_, first) = call(db_pool.clone(), "GET", &format!("{}?version=1", uri), None).await;
+        assert_eq!(first["subject"], "s1");
+        let (_, versions) = call(db_pool.clone(), "GET", &format!("{}/versions", uri), None).await;
+        assert_eq!(versions.as_array().unwrap().len(), 2);
+        let (_, templates) = call(db_pool.clone(), "GET", "/v1/templates", None).await;
+        assert_eq!(templates[0]["subject"], "s2");
+
+        let (status, _) = call(db_pool.clone(), "DELETE", &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(db_pool.clone(), "GET", &uri, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_create_template_rejects_invalid_syntax() {
+        let db_pool = db_pool().await;
+        let (status, _) = call(
+            db_pool,
+            "POST",
+            "/v1/templates",
+            Some(serde_json::json!({"name": "bad", "subject": "{{#if}}", "content": "c"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,278 @@
+#[cfg(test)]
+mod tests {
+    use crate::models::request::{EmailMessageStatus, EmailRequest};
+    use crate::tests::{call, db_pool};
+
+    #[tokio::test]
+    async fn test_topics_are_registered_and_listed() {
+        // 1. Topics are registered by the first message that uses them, owned by the caller
+        // 2. Metadata can be added later without losing the creator
+        // 3. Listing pages newest first with request counts and sent times
+        let db_pool = db_pool().await;
+        let (_, body) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/messages",
+            Some(serde_json::json!({
+                "messages": [
+                    {"topic_id": "spring-sale", "emails": ["a@example.com", "b@example.com"], "subject": "s", "content": "c"},
+                    {"emails": ["no-topic@example.com"], "subject": "s", "content": "c"}
+                ],
+                "scheduled_at": "2099-01-01 00:00:00"
+            })),
+        )
+        .await;
+        let request_id = body["results"][0]["request_id"].as_i64().unwrap() as i32;
+
+        let (status, body) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/topics",
+            Some(serde_json::json!({
+                "topic_id": "spring-sale",
+                "name": "Spring sale",
+                "description": "Seasonal campaign",
+                "tags": ["marketing", "seasonal"]
+            })),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(body["name"], "Spring sale");
+        assert_eq!(body["created_by"], "tester");
+        assert_eq!(body["tags"], serde_json::json!(["marketing", "seasonal"]));
+
+        let (status, _) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/topics",
+            Some(serde_json::json!({"topic_id": "newsletter"})),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        // A send moves the first/last sent times
+        EmailRequest {
+            id: Some(request_id),
+            topic_id: Some("spring-sale".to_string()),
+            email: "a@example.com".to_string(),
+            subject: "s".to_string(),
+            content: "c".to_string(),
+            scheduled_at: None,
+            status: EmailMessageStatus::Sent as i32,
+            error: None,
+            message_id: Some("ses-topic".to_string()),
+            leased_until: None,
+            lease_id: None,
+            template_id: None,
+            template_version: None,
+            track_clicks: true,
+        }
+        .update(&db_pool)
+        .await
+        .unwrap();
+
+        let (status, body) = call(db_pool.clone(), "GET", "/v1/topics?limit=1", None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        let topics = body["topics"].as_array().unwrap();
+        assert_eq!(topics.len(), 1);
+        assert_eq!(topics[0]["topic_id"], "newsletter");
+        assert_eq!(topics[0]["counts"]["total"], 0);
+        let cursor = body["next_cursor"].as_i64().unwrap();
+
+        let (_, body) = call(
+            db_pool.clone(),
+            "GET",
+            &format!("/v1/topics?limit=1&cursor={}", cursor),
+            None,
+        )
+        .await;
+        let topics = body["topics"].as_array().unwrap();
+        assert_eq!(topics.len(), 1);
+        assert_eq!(topics[0]["topic_id"], "spring-sale");
+        assert_eq!(topics[0]["counts"]["total"], 2);
+        assert_eq!(topics[0]["counts"]["pending"], 1);
+        assert_eq!(topics[0]["counts"]["sent"], 1);
+        assert!(topics[0]["first_sent_at"].is_string());
+        assert_eq!(topics[0]["first_sent_at"], topics[0]["last_sent_at"]);
+        assert_eq!(body["next_cursor"], serde_json::Value::Null);
+
+        let (_, body) = call(db_pool, "GET", "/v1/topics/spring-sale", None).await;
+        assert_eq!(body["topic"]["name"], "Spring sale");
+        assert_eq!(body["request_counts"]["Sent"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_topic_is_not_registered_without_saved_requests() {
+        // A message whose recipients are all rejected before saving leaves no topic behind
+        let db_pool = db_pool().await;
+        let (_, body) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/messages",
+            Some(serde_json::json!({
+                "messages": [
+                    {"topic_id": "typo", "emails": ["not-an-email"], "subject": "s", "content": "c"}
+                ],
+                "scheduled_at": "2099-01-01 00:00:00"
+            })),
+        )
+        .await;
+        assert_eq!(body["rejected"], 1);
+
+        let topics: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM topics")
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert_eq!(topics, 0);
+    }
+
+    #[tokio::test]
+    async fn test_pause_resume_and_restart_topic() {
+        // 1. Pausing holds pending requests; claimed ones lose their claim so the receiver skips them
+        // 2. Messages created while paused are held as well
+        // 3. Resuming releases held requests; restarting brings stopped requests back
+        let db_pool = db_pool().await;
+        let status_of = |id: i64| {
+            let db_pool = db_pool.clone();
+            async move {
+                sqlx::query_scalar::<_, i32>("SELECT status FROM email_requests WHERE id = ?")
+                    .bind(id)
+                    .fetch_one(&db_pool)
+                    .await
+                    .unwrap()
+            }
+        };
+        let (_, body) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/messages",
+            Some(serde_json::json!({
+                "messages": [{"topic_id": "campaign", "emails": ["a@example.com", "b@example.com"], "subject": "s", "content": "c"}],
+                "scheduled_at": "2000-01-01 00:00:00"
+            })),
+        )
+        .await;
+        let waiting_id = body["results"][1]["request_id"].as_i64().unwrap();
+        // One request is already claimed and queued for the receiver
+        let mut claimed = EmailRequest::claim_due(&db_pool, 1, 60).await.unwrap();
+        let mut queued = claimed.pop().unwrap();
+
+        let (status, body) = call(db_pool.clone(), "POST", "/v1/topics/campaign/pause", None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(body["paused"], 2);
+        assert!(!queued.confirm_lease(&db_pool, 60).await.unwrap());
+        assert_eq!(
+            status_of(waiting_id).await,
+            EmailMessageStatus::Paused as i32
+        );
+        let (status, _) = call(db_pool.clone(), "POST", "/v1/topics/campaign/pause", None).await;
+        assert_eq!(status, axum::http::StatusCode::CONFLICT);
+
+        let (_, body) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/messages",
+            Some(serde_json::json!({
+                "messages": [{"topic_id": "campaign", "emails": ["c@example.com"], "subject": "s", "content": "c"}]
+            })),
+        )
+        .await;
+        assert_eq!(body["paused"], 1);
+        assert_eq!(body["results"][0]["status"], "paused");
+        assert!(EmailRequest::claim_due(&db_pool, 10, 60)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let (status, body) =
+            call(db_pool.clone(), "POST", "/v1/topics/campaign/resume", None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(body["resumed"], 3);
+        assert_eq!(
+            status_of(waiting_id).await,
+            EmailMessageStatus::Created as i32
+        );
+        let (status, _) = call(db_pool.clone(), "POST", "/v1/topics/campaign/resume", None).await;
+        assert_eq!(status, axum::http::StatusCode::CONFLICT);
+
+        let (status, _) = call(db_pool.clone(), "DELETE", "/v1/topics/campaign", None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(
+            status_of(waiting_id).await,
+            EmailMessageStatus::Stopped as i32
+        );
+        let (status, body) =
+            call(db_pool.clone(), "POST", "/v1/topics/campaign/restart", None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(body["restarted"], 3);
+        assert_eq!(
+            status_of(waiting_id).await,
+            EmailMessageStatus::Created as i32
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reschedule_topic() {
+        // Waiting requests move to the new time; sent or cancelled ones are left alone
+        let db_pool = db_pool().await;
+        let (_, body) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/messages",
+            Some(serde_json::json!({
+                "messages": [{"topic_id": "launch", "emails": ["a@example.com", "b@example.com", "c@example.com"], "subject": "s", "content": "c"}],
+                "scheduled_at": "2000-01-01 00:00:00"
+            })),
+        )
+        .await;
+        let cancelled_id = body["results"][2]["request_id"].as_i64().unwrap();
+        let (status, _) = call(
+            db_pool.clone(),
+            "DELETE",
+            &format!("/v1/messages/{}", cancelled_id),
+            None,
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        let (status, body) = call(
+            db_pool.clone(),
+            "PATCH",
+            "/v1/topics/launch",
+            Some(serde_json::json!({"scheduled_at": "2099-01-01 00:00:00"})),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(body["rescheduled"], 2);
+        let scheduled_at = body["scheduled_at"].as_str().unwrap().to_string();
+        assert!(EmailRequest::claim_due(&db_pool, 10, 60)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let (_, body) = call(
+            db_pool.clone(),
+            "GET",
+            "/v1/audit-logs?target_type=topic&target_id=launch",
+            None,
+        )
+        .await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        assert_eq!(body[0]["action"], "topic.reschedule");
+        assert_eq!(body[0]["details"]["scheduled_at"], scheduled_at);
+        assert_eq!(body[0]["details"]["count"], 2);
+
+        // Nothing left to reschedule in an unknown topic, and nothing is recorded
+        let (status, body) = call(
+            db_pool.clone(),
+            "PATCH",
+            "/v1/topics/unknown",
+            Some(serde_json::json!({"scheduled_at": "2099-01-01 00:00:00"})),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(body["rescheduled"], 0);
+        let (_, body) = call(db_pool, "GET", "/v1/audit-logs?target_type=topic", None).await;
+        assert_eq!(body.as_array().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,113 @@
+#[cfg(test)]
+mod tests {
+    use crate::tests::{call, db_pool};
+
+    #[tokio::test]
+    async fn test_webhook_crud() {
+        // Register, list and delete webhooks through the API
+        // 1. The generated secret is only returned on creation
+        // 2. Non-http(s) URLs and unknown event types are rejected
+        let db_pool = db_pool().await;
+        let (status, created) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/webhooks",
+            Some(serde_json::json!({
+                "url": "https://example.com/hooks/ses",
+                "topic_id": "topic_id",
+                "event_types": ["Bounce", "complaint"]
+            })),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::CREATED);
+        assert_eq!(created["secret"].as_str().unwrap().len(), 64);
+        assert_eq!(
+            created["event_types"],
+            serde_json::json!(["Bounce", "Complaint"])
+        );
+
+        let (status, listed) = call(db_pool.clone(), "GET", "/v1/webhooks", None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(listed.as_array().unwrap().len(), 1);
+        assert_eq!(listed[0]["topic_id"], "topic_id");
+        assert!(listed[0].get("secret").is_none());
+
+        let (status, _) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/webhooks",
+            Some(serde_json::json!({"url": "ftp://example.com"})),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+        let (status, body) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/webhooks",
+            Some(serde_json::json!({"url": "https://example.com", "event_types": ["Bounces"]})),
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+        assert_eq!(body["field"], "event_types");
+
+        let uri = format!("/v1/webhooks/{}", created["id"]);
+        let (status, _) = call(db_pool.clone(), "DELETE", &uri, None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        let (status, _) = call(db_pool.clone(), "DELETE", &uri, None).await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_list_and_retry_failed_deliveries() {
+        // Failed deliveries are listed by default and can be moved back to pending
+        let db_pool = db_pool().await;
+        sqlx::query(
+            r#"
+            INSERT INTO webhooks (id, url, secret) VALUES (1, 'http://127.0.0.1:9/hook', 'secret');
+            INSERT INTO webhook_deliveries (id, webhook_id, result_id, payload, status, attempts)
+            VALUES (1, 1, 1, '{}', 'failed', 8), (2, 1, 2, '{}', 'delivered', 1);
+            "#,
+        )
+        .execute(&db_pool)
+        .await
+        .unwrap();
+
+        let (status, failed) = call(db_pool.clone(), "GET", "/v1/webhooks/deliveries", None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        assert_eq!(failed.as_array().unwrap().len(), 1);
+        assert_eq!(failed[0]["id"], 1);
+        let (status, _) = call(
+            db_pool.clone(),
+            "GET",
+            "/v1/webhooks/deliveries?status=unknown",
+            None,
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::BAD_REQUEST);
+
+        let (status, _) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/webhooks/deliveries/1/retry",
+            None,
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+        let (status, _) = call(
+            db_pool.clone(),
+            "POST",
+            "/v1/webhooks/deliveries/2/retry",
+            None,
+        )
+        .await;
+        assert_eq!(status, axum::http::StatusCode::NOT_FOUND);
+        let (_, pending) = call(
+            db_pool,
+            "GET",
+            "/v1/webhooks/deliveries?status=pending",
+            None,
+        )
+        .await;
+        assert_eq!(pending[0]["attempts"], 0);
+    }
+}
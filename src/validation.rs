@@ -0,0 +1,148 @@
+use crate::errors::ApiError;
+use crate::models::request::{convert_scheduled_at, ScheduledAtError};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+
+/// MAX_EMAIL_LENGTH
+/// Longest address accepted (RFC 5321 path limit)
+pub const MAX_EMAIL_LENGTH: usize = 254;
+
+/// MAX_SUBJECT_LENGTH
+/// Longest subject accepted (RFC 5322 line limit)
+pub const MAX_SUBJECT_LENGTH: usize = 998;
+
+/// MAX_CONTENT_LENGTH
+/// Largest content accepted, in bytes; SES rejects messages over 10 MB after encoding
+pub const MAX_CONTENT_LENGTH: usize = 1024 * 1024;
+
+/// MAX_RECIPIENTS_PER_REQUEST
+/// Most recipients accepted in one creation request
+pub const MAX_RECIPIENTS_PER_REQUEST: usize = 10_000;
+
+/// required
+/// Rejects a missing or blank value
+pub fn required(field: &str, value: Option<&str>) -> Result<(), ApiError> {
+    match value {
+        Some(value) if !value.trim().is_empty() => Ok(()),
+        _ => Err(
+            ApiError::bad_request("required", format!("{} is required", field)).with_field(field),
+        ),
+    }
+}
+
+/// max_length
+/// Rejects a value longer than `max` bytes
+pub fn max_length(field: &str, value: &str, max: usize) -> Result<(), ApiError> {
+    if value.len() > max {
+        return Err(ApiError::bad_request(
+            "too_long",
+            format!("{} must be at most {} bytes", field, max),
+        )
+        .with_field(field));
+    }
+    Ok(())
+}
+
+/// email
+/// Rejects an address that is too long or not a valid address
+pub fn email(field: &str, value: &str) -> Result<(), ApiError> {
+    max_length(field, value, MAX_EMAIL_LENGTH)?;
+    if value.parse::<lettre::Address>().is_err() {
+        return Err(
+            ApiError::bad_request("invalid_email", "Invalid email address").with_field(field),
+        );
+    }
+    Ok(())
+}
+
+/// scheduled_at
+/// Converts the scheduled time to UTC, see `convert_scheduled_at`
+/// Errors about the time zone refer to the `timezone` field
+pub fn scheduled_at(value: Option<&str>, timezone: Option<&str>) -> Result<String, ApiError> {
+    convert_scheduled_at(value, timezone).map_err(|e| match e {
+        ScheduledAtError::InvalidFormat => ApiError::bad_request(
+            "invalid_format",
+            "scheduled_at must be RFC 3339 with an offset or YYYY-MM-DD HH:MM:SS",
+        )
+        .with_field("scheduled_at"),
+        ScheduledAtError::InvalidTimeZone => ApiError::bad_request(
+            "invalid_timezone",
+            "timezone must be an IANA time zone name, e.g. Asia/Seoul",
+        )
+        .with_field("timezone"),
+        ScheduledAtError::NonexistentLocalTime => ApiError::bad_request(
+            "nonexistent_local_time",
+            "scheduled_at is skipped by a daylight saving time transition in the time zone",
+        )
+        .with_field("scheduled_at"),
+        ScheduledAtError::AmbiguousLocalTime => ApiError::bad_request(
+            "ambiguous_local_time",
+            "scheduled_at is repeated by a daylight saving time transition in the time zone; use an offset",
+        )
+        .with_field("scheduled_at"),
+    })
+}
+
+/// utc_datetime
+/// Normalizes a date filter to the stored UTC format "YYYY-MM-DD HH:MM:SS"
+/// Accepts RFC 3339 with an offset, or "YYYY-MM-DD HH:MM:SS" and "YYYY-MM-DD" taken as UTC
+pub fn utc_datetime(field: &str, value: &str) -> Result<String, ApiError> {
+    let parsed = DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d").map(|date| date.and_time(NaiveTime::MIN))
+        });
+    match parsed {
+        Ok(dt) => Ok(dt.format("%Y-%m-%d %H:%M:%S").to_string()),
+        Err(_) => Err(ApiError::bad_request(
+            "invalid_format",
+            format!(
+                "{} must be RFC 3339, YYYY-MM-DD HH:MM:SS or YYYY-MM-DD",
+                field
+            ),
+        )
+        .with_field(field)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation_errors() {
+        assert!(required("subject", Some("Hello")).is_ok());
+        let error = required("messages[0].subject", Some("  ")).unwrap_err();
+        assert_eq!(error.code, "required");
+        assert_eq!(error.field.as_deref(), Some("messages[0].subject"));
+        assert_eq!(required("content", None).unwrap_err().code, "required");
+
+        assert!(email("email", "user@example.com").is_ok());
+        assert_eq!(
+            email("email", "not-an-email").unwrap_err().code,
+            "invalid_email"
+        );
+        let long = format!("{}@example.com", "a".repeat(MAX_EMAIL_LENGTH));
+        assert_eq!(email("email", &long).unwrap_err().code, "too_long");
+
+        assert_eq!(
+            utc_datetime("created_from", "2024-01-01T09:00:00+09:00").unwrap(),
+            "2024-01-01 00:00:00"
+        );
+        assert_eq!(
+            utc_datetime("created_from", "2024-01-01").unwrap(),
+            "2024-01-01 00:00:00"
+        );
+        assert_eq!(
+            utc_datetime("created_from", "yesterday").unwrap_err().code,
+            "invalid_format"
+        );
+
+        let error = scheduled_at(Some("tomorrow"), None).unwrap_err();
+        assert_eq!(error.code, "invalid_format");
+        assert_eq!(error.field.as_deref(), Some("scheduled_at"));
+        let error = scheduled_at(Some("2024-01-01 09:00:00"), Some("KST")).unwrap_err();
+        assert_eq!(error.code, "invalid_timezone");
+        assert_eq!(error.field.as_deref(), Some("timezone"));
+    }
+}